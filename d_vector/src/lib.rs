@@ -155,6 +155,55 @@ impl<const D: usize> Sub<&DVector<D>> for &DVector<D> {
     }
 }
 
+impl<const D: usize> Serialize for DVector<D> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut seq = serializer.serialize_seq(Some(D))?;
+        for e in self.components.iter() {
+            seq.serialize_element(e)?;
+        }
+        seq.end()
+    }
+}
+
+struct DVectorVisitor<const D: usize>;
+
+impl<'de, const D: usize> Visitor<'de> for DVectorVisitor<D> {
+    type Value = DVector<D>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let expect = format!("an array of {} floats", D);
+        formatter.write_str(&expect)
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut components = [0 as Real; D];
+        for c in components.iter_mut() {
+            let val: Option<Real> = seq.next_element()?;
+            if let Some(component) = val {
+                *c = component;
+            } else {
+                return Err(de::Error::invalid_length(D, &self));
+            }
+        }
+        Ok(DVector::from(components))
+    }
+}
+
+impl<'de, const D: usize> Deserialize<'de> for DVector<D> {
+    fn deserialize<De>(deserializer: De) -> Result<Self, De::Error>
+    where
+        De: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(DVectorVisitor)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -209,52 +258,3 @@ mod tests {
         assert_eq!(&b * &b, b.square_length());
     }
 }
-
-impl<const D: usize> Serialize for DVector<D> {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let mut seq = serializer.serialize_seq(Some(D))?;
-        for e in self.components.iter() {
-            seq.serialize_element(e)?;
-        }
-        seq.end()
-    }
-}
-
-struct DVectorVisitor<const D: usize>;
-
-impl<'de, const D: usize> Visitor<'de> for DVectorVisitor<D> {
-    type Value = DVector<D>;
-
-    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        let expect = format!("an array of {} floats", D);
-        formatter.write_str(&expect)
-    }
-
-    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
-    where
-        A: SeqAccess<'de>,
-    {
-        let mut components = [0 as Real; D];
-        for c in components.iter_mut() {
-            let val: Option<Real> = seq.next_element()?;
-            if let Some(component) = val {
-                *c = component;
-            } else {
-                return Err(de::Error::invalid_length(D, &self));
-            }
-        }
-        Ok(DVector::from(components))
-    }
-}
-
-impl<'de, const D: usize> Deserialize<'de> for DVector<D> {
-    fn deserialize<De>(deserializer: De) -> Result<Self, De::Error>
-    where
-        De: serde::Deserializer<'de>,
-    {
-        deserializer.deserialize_seq(DVectorVisitor)
-    }
-}
@@ -25,15 +25,15 @@ impl Default for TwoState {
 }
 
 impl MolecularState<2> for TwoState {
-    fn get_pos(&self) -> RefMut<Vec<DVector<2>>> {
+    fn get_pos(&self) -> RefMut<'_, Vec<DVector<2>>> {
         self.inner.get_pos()
     }
 
-    fn get_vel(&self) -> RefMut<Vec<DVector<2>>> {
+    fn get_vel(&self) -> RefMut<'_, Vec<DVector<2>>> {
         self.inner.get_vel()
     }
 
-    fn get_acc(&self) -> RefMut<Vec<DVector<2>>> {
+    fn get_acc(&self) -> RefMut<'_, Vec<DVector<2>>> {
         self.inner.get_acc()
     }
 
@@ -41,11 +41,11 @@ impl MolecularState<2> for TwoState {
         let pos_ref = self.get_pos();
         let vel_ref = self.get_vel();
         let acc_ref = self.get_acc();
-        let left_position = pos_ref.get(0).unwrap();
+        let left_position = pos_ref.first().unwrap();
         let right_position = pos_ref.get(1).unwrap();
-        let left_velocity = vel_ref.get(0).unwrap();
+        let left_velocity = vel_ref.first().unwrap();
         let right_velocity = vel_ref.get(1).unwrap();
-        let left_acceleration = acc_ref.get(0).unwrap();
+        let left_acceleration = acc_ref.first().unwrap();
         let right_acceleration = acc_ref.get(1).unwrap();
         assert_eq!(left_position, &((-1.) * right_position));
         assert_eq!(left_velocity, &((-1.) * right_velocity));
@@ -0,0 +1,445 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    boundaries::BoundaryConditions,
+    potential::{PotentialEnergy, VirialTensor},
+    state,
+    thermostat::degrees_of_freedom,
+};
+use d_vector::{DVector, Real};
+use std::fmt::Debug;
+
+pub trait Barostat<const D: usize>: Debug + Send {
+    /// May rescale the box and positions before the integrator step. The integrator
+    /// kicks with the stored forces, which do not depend on the positions, before it
+    /// drifts and recomputes them, so the forces never need an extra evaluation.
+    fn before_step(
+        &mut self,
+        delta_t: Real,
+        pos: &mut [DVector<D>],
+        vel: &mut [DVector<D>],
+        masses: &[Real],
+        boundaries: &mut dyn BoundaryConditions<D>,
+        potential_energy: &dyn PotentialEnergy<D>,
+    );
+    fn after_step(
+        &mut self,
+        delta_t: Real,
+        vel: &mut [DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        potential_energy: &dyn PotentialEnergy<D>,
+    ) {
+    }
+    fn conserved_energy(&self) -> Real {
+        0.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Coupling {
+    #[default]
+    Isotropic,
+    PerAxis,
+}
+
+pub fn volume<const D: usize>(boundaries: &dyn BoundaryConditions<D>) -> Option<Real> {
+    boundaries.box_size().map(|size| size.iter().product())
+}
+
+fn mvv_sum<const D: usize>(vel: &[DVector<D>], masses: &[Real]) -> Real {
+    vel.iter()
+        .enumerate()
+        .map(|(j, v)| state::mass(masses, j) * v.square_length())
+        .sum()
+}
+
+/// `P = (Σ m v² + Σ r · f) / (D V)`.
+pub fn pressure<const D: usize>(
+    vel: &[DVector<D>],
+    masses: &[Real],
+    virial: Real,
+    volume: Real,
+) -> Real {
+    (mvv_sum(vel, masses) + virial) / (D as Real * volume)
+}
+
+/// Pressure tensor `P_ab = (Σ m v_a v_b + Σ r_a f_b) / V`.
+/// Potentials without a virial tensor contribute `virial_sum / D` to each diagonal element.
+pub fn pressure_tensor<const D: usize>(
+    vel: &[DVector<D>],
+    masses: &[Real],
+    potential_energy: &dyn PotentialEnergy<D>,
+    volume: Real,
+) -> VirialTensor<D> {
+    let mut result = [[0.; D]; D];
+    for (j, v) in vel.iter().enumerate() {
+        let m = state::mass(masses, j);
+        for (row, a) in result.iter_mut().zip(v.components()) {
+            for (p, b) in row.iter_mut().zip(v.components()) {
+                *p += m * a * b;
+            }
+        }
+    }
+    match potential_energy.virial_tensor() {
+        Some(w) => {
+            for (row, w_row) in result.iter_mut().zip(w.iter()) {
+                for (p, w) in row.iter_mut().zip(w_row.iter()) {
+                    *p += w;
+                }
+            }
+        }
+        None => {
+            let w = potential_energy.virial_sum() / D as Real;
+            for (i, row) in result.iter_mut().enumerate() {
+                row[i] += w;
+            }
+        }
+    }
+    for p in result.iter_mut().flat_map(|row| row.iter_mut()) {
+        *p /= volume;
+    }
+    result
+}
+
+/// Diagonal of the pressure tensor, `P_aa = (Σ m v_a² + Σ r_a f_a) / V`.
+pub fn pressure_diagonal<const D: usize>(
+    vel: &[DVector<D>],
+    masses: &[Real],
+    potential_energy: &dyn PotentialEnergy<D>,
+    volume: Real,
+) -> [Real; D] {
+    let tensor = pressure_tensor(vel, masses, potential_energy, volume);
+    let mut result = [0.; D];
+    for (i, p) in result.iter_mut().enumerate() {
+        *p = tensor[i][i];
+    }
+    result
+}
+
+fn pressure_components<const D: usize>(
+    coupling: Coupling,
+    vel: &[DVector<D>],
+    masses: &[Real],
+    potential_energy: &dyn PotentialEnergy<D>,
+    volume: Real,
+) -> [Real; D] {
+    match coupling {
+        Coupling::Isotropic => [pressure(vel, masses, potential_energy.virial_sum(), volume); D],
+        Coupling::PerAxis => pressure_diagonal(vel, masses, potential_energy, volume),
+    }
+}
+
+fn scale_box<const D: usize>(
+    factors: &[Real; D],
+    pos: &mut [DVector<D>],
+    boundaries: &mut dyn BoundaryConditions<D>,
+) {
+    let Some(size) = boundaries.box_size() else {
+        return;
+    };
+    let mut new_size = *size;
+    for (s, f) in new_size.iter_mut().zip(factors.iter()) {
+        *s *= f;
+    }
+    boundaries.resize(new_size);
+    for position in pos.iter_mut() {
+        let mut components = *position.components();
+        for (c, f) in components.iter_mut().zip(factors.iter()) {
+            *c *= f;
+        }
+        *position = DVector::from(components);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NoBarostat;
+
+impl<const D: usize> Barostat<D> for NoBarostat {
+    fn before_step(
+        &mut self,
+        _: Real,
+        _: &mut [DVector<D>],
+        _: &mut [DVector<D>],
+        _: &[Real],
+        _: &mut dyn BoundaryConditions<D>,
+        _: &dyn PotentialEnergy<D>,
+    ) {
+    }
+}
+
+/// Weak coupling: the box is scaled by `μ = 1 - β Δt / (D τ) (P0 - P)` along each axis.
+#[derive(Debug)]
+pub struct Berendsen {
+    pressure: Real,
+    tau: Real,
+    compressibility: Real,
+    coupling: Coupling,
+}
+
+impl Berendsen {
+    pub fn new(pressure: Real, tau: Real) -> Self {
+        Self {
+            pressure,
+            tau,
+            compressibility: 1.,
+            coupling: Coupling::default(),
+        }
+    }
+
+    pub fn compressibility(mut self, compressibility: Real) -> Self {
+        self.compressibility = compressibility;
+        self
+    }
+
+    pub fn coupling(mut self, coupling: Coupling) -> Self {
+        self.coupling = coupling;
+        self
+    }
+}
+
+impl<const D: usize> Barostat<D> for Berendsen {
+    fn before_step(
+        &mut self,
+        delta_t: Real,
+        pos: &mut [DVector<D>],
+        vel: &mut [DVector<D>],
+        masses: &[Real],
+        boundaries: &mut dyn BoundaryConditions<D>,
+        potential_energy: &dyn PotentialEnergy<D>,
+    ) {
+        let Some(volume) = volume(&*boundaries) else {
+            return;
+        };
+        let k = self.compressibility * delta_t / (D as Real * self.tau);
+        let mut factors = pressure_components(self.coupling, vel, masses, potential_energy, volume);
+        for f in factors.iter_mut() {
+            *f = 1. - k * (self.pressure - *f);
+        }
+        scale_box(&factors, pos, boundaries);
+    }
+}
+
+/// Martyna–Tobias–Klein (Andersen-type) piston acting on the logarithmic box
+/// lengths, with piston mass `W = (N_f + D) T τ²`. The piston is kicked and the
+/// velocities rescaled half a step before and after every integrator step; the
+/// box is scaled in between, ahead of the integrator's drift.
+#[derive(Debug)]
+pub struct MartynaTobiasKlein {
+    pressure: Real,
+    temperature: Real,
+    tau: Real,
+    coupling: Coupling,
+    v_eps: Vec<Real>,
+    mass: Real,
+    volume: Real,
+}
+
+impl MartynaTobiasKlein {
+    pub fn new(pressure: Real, temperature: Real, tau: Real) -> Self {
+        Self {
+            pressure,
+            temperature,
+            tau,
+            coupling: Coupling::default(),
+            v_eps: Vec::new(),
+            mass: 0.,
+            volume: 0.,
+        }
+    }
+
+    pub fn coupling(mut self, coupling: Coupling) -> Self {
+        self.coupling = coupling;
+        self
+    }
+
+    fn half_step<const D: usize>(
+        &mut self,
+        half_delta_t: Real,
+        vel: &mut [DVector<D>],
+        masses: &[Real],
+        potential_energy: &dyn PotentialEnergy<D>,
+        kick_first: bool,
+    ) {
+        if self.v_eps.len() != D {
+            self.v_eps = vec![0.; D];
+        }
+        let n_f = degrees_of_freedom::<D>(vel.len());
+        if n_f == 0. || self.volume == 0. {
+            return;
+        }
+        self.mass = (n_f + D as Real) * self.temperature * self.tau * self.tau;
+        if kick_first {
+            self.kick(half_delta_t, n_f, vel, masses, potential_energy);
+            self.scale_velocities(half_delta_t, n_f, vel);
+        } else {
+            self.scale_velocities(half_delta_t, n_f, vel);
+            self.kick(half_delta_t, n_f, vel, masses, potential_energy);
+        }
+    }
+
+    fn kick<const D: usize>(
+        &mut self,
+        half_delta_t: Real,
+        n_f: Real,
+        vel: &[DVector<D>],
+        masses: &[Real],
+        potential_energy: &dyn PotentialEnergy<D>,
+    ) {
+        let vv_sum = mvv_sum(vel, masses);
+        match self.coupling {
+            Coupling::Isotropic => {
+                let p = pressure(vel, masses, potential_energy.virial_sum(), self.volume);
+                let g = D as Real * self.volume * (p - self.pressure) + D as Real / n_f * vv_sum;
+                self.v_eps[0] += half_delta_t * g / self.mass;
+            }
+            Coupling::PerAxis => {
+                let p = pressure_diagonal(vel, masses, potential_energy, self.volume);
+                for (v_eps, p) in self.v_eps.iter_mut().zip(p.iter()) {
+                    let g = self.volume * (p - self.pressure) + vv_sum / n_f;
+                    *v_eps += half_delta_t * g / self.mass;
+                }
+            }
+        }
+    }
+
+    fn strain_rates<const D: usize>(&self) -> [Real; D] {
+        match self.coupling {
+            Coupling::Isotropic => [self.v_eps[0]; D],
+            Coupling::PerAxis => {
+                let mut result = [0.; D];
+                result.copy_from_slice(&self.v_eps[..D]);
+                result
+            }
+        }
+    }
+
+    fn scale_velocities<const D: usize>(
+        &self,
+        half_delta_t: Real,
+        n_f: Real,
+        vel: &mut [DVector<D>],
+    ) {
+        let rates = self.strain_rates::<D>();
+        let trace: Real = rates.iter().sum();
+        let mut factors = [0.; D];
+        for (f, rate) in factors.iter_mut().zip(rates.iter()) {
+            *f = (-(rate + trace / n_f) * half_delta_t).exp();
+        }
+        for velocity in vel.iter_mut() {
+            let mut components = *velocity.components();
+            for (c, f) in components.iter_mut().zip(factors.iter()) {
+                *c *= f;
+            }
+            *velocity = DVector::from(components);
+        }
+    }
+}
+
+impl<const D: usize> Barostat<D> for MartynaTobiasKlein {
+    fn before_step(
+        &mut self,
+        delta_t: Real,
+        pos: &mut [DVector<D>],
+        vel: &mut [DVector<D>],
+        masses: &[Real],
+        boundaries: &mut dyn BoundaryConditions<D>,
+        potential_energy: &dyn PotentialEnergy<D>,
+    ) {
+        let Some(old_volume) = volume(&*boundaries) else {
+            return;
+        };
+        self.volume = old_volume;
+        self.half_step(delta_t / 2., vel, masses, potential_energy, true);
+        if self.v_eps.len() != D {
+            return;
+        }
+        let mut factors = self.strain_rates::<D>();
+        for f in factors.iter_mut() {
+            *f = (*f * delta_t).exp();
+        }
+        scale_box(&factors, pos, boundaries);
+        self.volume = volume(&*boundaries).unwrap();
+    }
+
+    fn after_step(
+        &mut self,
+        delta_t: Real,
+        vel: &mut [DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        potential_energy: &dyn PotentialEnergy<D>,
+    ) {
+        if volume(boundaries).is_some() && self.v_eps.len() == D {
+            self.half_step(delta_t / 2., vel, masses, potential_energy, false);
+        }
+    }
+
+    fn conserved_energy(&self) -> Real {
+        let kinetic: Real = match self.coupling {
+            _ if self.v_eps.is_empty() => 0.,
+            Coupling::Isotropic => 0.5 * self.mass * self.v_eps[0] * self.v_eps[0],
+            Coupling::PerAxis => 0.5 * self.mass * self.v_eps.iter().map(|v| v * v).sum::<Real>(),
+        };
+        kinetic + self.pressure * self.volume
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        initial_state,
+        job::{Job, JobSetup},
+        langevin::Langevin,
+        lennard_jones::LennardJones,
+    };
+
+    fn wca_job(barostat: impl Barostat<3> + 'static, langevin: bool) -> Job<3> {
+        let (boundaries, pos) = initial_state::cubic_lattice(216, 0.8);
+        let setup = JobSetup::build()
+            .delta_t(0.005)
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .random_vel(1.)
+            .potential(LennardJones::new((2 as Real).powf(1. / 6.)))
+            .barostat(barostat);
+        if langevin {
+            setup.integrator(Langevin::new(1., 1.).seed(11)).job()
+        } else {
+            setup.job()
+        }
+    }
+
+    fn mean_pressure(job: &mut Job<3>, steps: usize) -> Real {
+        let mut sum = 0.;
+        for _ in 0..steps {
+            job.run(1);
+            sum += job.pressure().unwrap();
+        }
+        sum / steps as Real
+    }
+
+    #[test]
+    fn berendsen_reaches_target_pressure() {
+        for coupling in [Coupling::Isotropic, Coupling::PerAxis] {
+            let mut job = wca_job(Berendsen::new(2., 0.5).coupling(coupling), true);
+            let v0 = job.volume().unwrap();
+            job.run(1000);
+            let p = mean_pressure(&mut job, 1000);
+            assert!((p - 2.).abs() < 0.2);
+            assert!(job.volume().unwrap() > v0);
+        }
+    }
+
+    #[test]
+    fn mtk_conserves_enthalpy() {
+        let mut job = wca_job(MartynaTobiasKlein::new(2., 1., 0.5), false);
+        job.run(10);
+        let start = job.extended_energy();
+        let v0 = job.volume().unwrap();
+        job.run(1000);
+        assert!((job.extended_energy() - start).abs() < 0.01 * start.abs());
+        assert!((job.volume().unwrap() - v0).abs() > 0.01 * v0);
+    }
+}
@@ -7,8 +7,12 @@ use std::{
     ops::{AddAssign, SubAssign},
 };
 
-pub trait BoundaryConditions<const D: usize>: Debug {
+pub trait BoundaryConditions<const D: usize>: Debug + Send + Sync {
     fn wrap(&self, pos: &mut DVector<D>);
+    fn box_size(&self) -> Option<&[Real; D]> {
+        None
+    }
+    fn resize(&mut self, dimensions: [Real; D]) {}
 }
 
 #[derive(Debug)]
@@ -34,6 +38,14 @@ impl<const D: usize> Region<D> {
     pub fn dimensions(&self) -> &[Real; D] {
         self.inner.components()
     }
+
+    pub fn set_dimensions(&mut self, dimensions: [Real; D]) {
+        self.inner = DVector::from(dimensions);
+    }
+
+    pub fn volume(&self) -> Real {
+        self.dimensions().iter().product()
+    }
 }
 
 impl<const D: usize> BoundaryConditions<D> for Region<D> {
@@ -48,9 +60,108 @@ impl<const D: usize> BoundaryConditions<D> for Region<D> {
         }
         position.add_assign(DVector::from(shift));
     }
+
+    fn box_size(&self) -> Option<&[Real; D]> {
+        Some(self.dimensions())
+    }
+
+    fn resize(&mut self, dimensions: [Real; D]) {
+        self.set_dimensions(dimensions);
+    }
+}
+
+/// Periodic image crossings and unwrapped positions of every molecule, found from the
+/// minimum-image displacements between consecutive wrapped positions; a molecule must
+/// not move more than half a box per update. Unwrapping follows the box as it is resized.
+#[derive(Debug, Clone, Default)]
+pub struct Images<const D: usize> {
+    counts: Vec<[i32; D]>,
+    last: Vec<DVector<D>>,
+    unwrapped: Vec<DVector<D>>,
+}
+
+impl<const D: usize> Images<D> {
+    pub fn track(&mut self, pos: &[DVector<D>], boundaries: &dyn BoundaryConditions<D>) {
+        if self.counts.len() != pos.len() {
+            self.counts = vec![[0; D]; pos.len()];
+            self.last = pos.to_vec();
+            self.unwrapped = pos.to_vec();
+            return;
+        }
+        let size = boundaries.box_size().copied();
+        for (((count, last), unwrapped), position) in self
+            .counts
+            .iter_mut()
+            .zip(self.last.iter_mut())
+            .zip(self.unwrapped.iter_mut())
+            .zip(pos.iter())
+        {
+            let shift = position - &*last;
+            let mut dr = shift.clone();
+            boundaries.wrap(&mut dr);
+            if let Some(size) = size {
+                for (i, c) in count.iter_mut().enumerate() {
+                    *c += ((dr.components()[i] - shift.components()[i]) / size[i]).round() as i32;
+                }
+            }
+            *unwrapped += &dr;
+            *last = position.clone();
+        }
+    }
+
+    pub fn counts(&self) -> &[[i32; D]] {
+        &self.counts
+    }
+
+    pub fn unwrapped(&self) -> &[DVector<D>] {
+        &self.unwrapped
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+
+    #[test]
+    fn resize() {
+        let mut region = Region::new([2., 4.]);
+        assert_eq!(8., region.volume());
+        BoundaryConditions::resize(&mut region, [3., 5.]);
+        assert_eq!(&[3., 5.], region.dimensions());
+        let mut p = DVector::from([1.75, -2.75]);
+        region.wrap(&mut p);
+        assert_eq!(&[-1.25, 2.25], p.components());
+    }
+
+    #[test]
+    fn images_follow_crossings() {
+        let region = Region::new([2.]);
+        let mut images = Images::default();
+        let mut pos = vec![DVector::from([0.5])];
+        images.track(&pos, &region);
+        for _ in 0..10 {
+            pos[0] += DVector::from([0.3]);
+            region.wrap(&mut pos[0]);
+            images.track(&pos, &region);
+        }
+        assert_eq!(&[[2]], images.counts());
+        assert!((images.unwrapped()[0].components()[0] - 3.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn images_follow_resized_box() {
+        let mut region = Region::new([2.]);
+        let mut images = Images::default();
+        let mut pos = vec![DVector::from([0.9])];
+        images.track(&pos, &region);
+        for _ in 0..5 {
+            let side = region.dimensions()[0] * 1.1;
+            BoundaryConditions::resize(&mut region, [side]);
+            pos[0] += DVector::from([0.3]);
+            region.wrap(&mut pos[0]);
+            images.track(&pos, &region);
+        }
+        assert_eq!(&[[1]], images.counts());
+        assert!((images.unwrapped()[0].components()[0] - 2.4).abs() < 1e-5);
+    }
 }
@@ -0,0 +1,232 @@
+#![allow(unused, dead_code)]
+
+use crate::boundaries::BoundaryConditions;
+use d_vector::{DVector, Real};
+use std::{ops::Range, sync::MutexGuard};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PairSearch {
+    AllPairs,
+    #[default]
+    Cells,
+}
+
+/// Calls `f(j1, j2)` once for every pair of molecules that may lie within `r_cut`.
+pub fn for_each_pair<const D: usize>(
+    pos: &[DVector<D>],
+    boundaries: &dyn BoundaryConditions<D>,
+    r_cut: Real,
+    search: PairSearch,
+    f: impl FnMut(usize, usize),
+) {
+    Pairs::new(pos, boundaries, r_cut, search).for_each_pair(f)
+}
+
+/// Candidate pairs split into independent units of work: one molecule of the
+/// all-pairs loop, one cell of the cell list or one entry of a neighbor list.
+#[derive(Debug)]
+pub enum Pairs<'a, const D: usize> {
+    All(usize),
+    Cells(CellList<D>),
+    Listed(MutexGuard<'a, Vec<(usize, usize)>>),
+}
+
+impl<'a, const D: usize> Pairs<'a, D> {
+    /// Falls back to the all-pairs loop when the boundaries are not a periodic box
+    /// or the box is too small to hold at least three cells along every axis.
+    pub fn new(
+        pos: &[DVector<D>],
+        boundaries: &dyn BoundaryConditions<D>,
+        r_cut: Real,
+        search: PairSearch,
+    ) -> Self {
+        let cells = match search {
+            PairSearch::Cells => boundaries
+                .box_size()
+                .and_then(|size| CellList::build(pos, size, r_cut)),
+            PairSearch::AllPairs => None,
+        };
+        match cells {
+            Some(cells) => Self::Cells(cells),
+            None => Self::All(pos.len()),
+        }
+    }
+
+    pub fn units(&self) -> usize {
+        match self {
+            Self::All(n_mol) => *n_mol,
+            Self::Cells(cells) => cells.head.len(),
+            Self::Listed(pairs) => pairs.len(),
+        }
+    }
+
+    pub fn for_each_pair(&self, f: impl FnMut(usize, usize)) {
+        self.for_each_pair_in(0..self.units(), f)
+    }
+
+    pub fn for_each_pair_in(&self, units: Range<usize>, mut f: impl FnMut(usize, usize)) {
+        match self {
+            Self::All(n_mol) => {
+                for j1 in units {
+                    for j2 in (j1 + 1)..*n_mol {
+                        f(j1, j2);
+                    }
+                }
+            }
+            Self::Cells(cells) => cells.for_each_pair_in(units, f),
+            Self::Listed(pairs) => {
+                for &(j1, j2) in pairs[units].iter() {
+                    f(j1, j2);
+                }
+            }
+        }
+    }
+}
+
+/// Rapaport-style cell subdivision: `head[c]` is the first molecule in cell `c`,
+/// `next[j]` is the molecule following `j` in the same cell.
+#[derive(Debug)]
+pub struct CellList<const D: usize> {
+    cells: [usize; D],
+    head: Vec<Option<usize>>,
+    next: Vec<Option<usize>>,
+    offsets: Vec<[isize; D]>,
+}
+
+impl<const D: usize> CellList<D> {
+    pub fn build(pos: &[DVector<D>], region: &[Real; D], r_cut: Real) -> Option<Self> {
+        let mut cells = [0; D];
+        for (n, size) in cells.iter_mut().zip(region.iter()) {
+            *n = (size / r_cut) as usize;
+            if *n < 3 {
+                return None;
+            }
+        }
+        let n_cells = cells.iter().product();
+        let mut result = Self {
+            cells,
+            head: vec![None; n_cells],
+            next: vec![None; pos.len()],
+            offsets: half_stencil(),
+        };
+        for (j, position) in pos.iter().enumerate() {
+            let c = result.cell_of(position, region);
+            result.next[j] = result.head[c];
+            result.head[c] = Some(j);
+        }
+        Some(result)
+    }
+
+    pub fn cells(&self) -> &[usize; D] {
+        &self.cells
+    }
+
+    fn cell_of(&self, position: &DVector<D>, region: &[Real; D]) -> usize {
+        let mut index = 0;
+        for i in (0..D).rev() {
+            let n = self.cells[i];
+            let x = (position.components()[i] / region[i] + 0.5) * n as Real;
+            let c = (x.floor() as isize).rem_euclid(n as isize) as usize;
+            index = index * n + c;
+        }
+        index
+    }
+
+    fn coordinates(&self, mut index: usize) -> [usize; D] {
+        let mut result = [0; D];
+        for (c, n) in result.iter_mut().zip(self.cells.iter()) {
+            *c = index % n;
+            index /= n;
+        }
+        result
+    }
+
+    fn index(&self, coordinates: &[usize; D]) -> usize {
+        let mut index = 0;
+        for i in (0..D).rev() {
+            index = index * self.cells[i] + coordinates[i];
+        }
+        index
+    }
+
+    fn molecules(&self, c: usize) -> impl Iterator<Item = usize> + '_ {
+        std::iter::successors(self.head[c], move |&j| self.next[j])
+    }
+
+    pub fn for_each_pair(&self, f: impl FnMut(usize, usize)) {
+        self.for_each_pair_in(0..self.head.len(), f)
+    }
+
+    pub fn for_each_pair_in(&self, cells: Range<usize>, mut f: impl FnMut(usize, usize)) {
+        for c1 in cells {
+            for j1 in self.molecules(c1) {
+                for j2 in self.molecules(c1) {
+                    if j1 < j2 {
+                        f(j1, j2);
+                    }
+                }
+            }
+            let base = self.coordinates(c1);
+            for offset in self.offsets.iter() {
+                let mut neighbor = [0; D];
+                for i in 0..D {
+                    let n = self.cells[i] as isize;
+                    neighbor[i] = (base[i] as isize + offset[i]).rem_euclid(n) as usize;
+                }
+                let c2 = self.index(&neighbor);
+                for j1 in self.molecules(c1) {
+                    for j2 in self.molecules(c2) {
+                        f(j1, j2);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Half of the 3^D - 1 neighbor offsets, so that every pair of adjacent cells is visited once.
+fn half_stencil<const D: usize>() -> Vec<[isize; D]> {
+    let total = 3_usize.pow(D as u32);
+    let mut result = Vec::with_capacity(total / 2);
+    for mut k in 0..total {
+        let mut offset = [0; D];
+        for o in offset.iter_mut() {
+            *o = (k % 3) as isize - 1;
+            k /= 3;
+        }
+        if offset.iter().find(|o| **o != 0).is_some_and(|o| *o > 0) {
+            result.push(offset);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_stencil_size() {
+        assert_eq!(1, half_stencil::<1>().len());
+        assert_eq!(4, half_stencil::<2>().len());
+        assert_eq!(13, half_stencil::<3>().len());
+    }
+
+    #[test]
+    fn too_small_box_has_no_cells() {
+        let pos = vec![DVector::from([0., 0.])];
+        assert!(CellList::build(&pos, &[7., 5.], 2.5).is_none());
+        let cells = CellList::build(&pos, &[7.5, 10.], 2.5).unwrap();
+        assert_eq!(&[3, 4], cells.cells());
+    }
+
+    #[test]
+    fn positions_outside_the_box_wrap() {
+        // 0.5 beyond the +x face, 2.1 away from the other molecule across it
+        let pos = vec![DVector::from([-2.4, 0.]), DVector::from([5.5, 0.])];
+        let cells = CellList::build(&pos, &[10., 10.], 2.5).unwrap();
+        let mut pairs = Vec::new();
+        cells.for_each_pair(|j1, j2| pairs.push((j1.min(j2), j1.max(j2))));
+        assert_eq!(vec![(0, 1)], pairs);
+    }
+}
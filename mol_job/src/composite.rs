@@ -0,0 +1,227 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    boundaries::BoundaryConditions,
+    cutoff::TailCorrection,
+    neighbor_list::NeighborList,
+    potential::{PotentialEnergy, VirialTensor},
+};
+use d_vector::{DVector, Real};
+use std::sync::Mutex;
+
+/// Sum of several potential energy terms, e.g. pair interactions, bonds, external
+/// fields and electrostatics. Every term computes its forces into a scratch array
+/// that is then added to `acc`.
+#[derive(Debug, Default)]
+pub struct CompositePotential<const D: usize> {
+    terms: Vec<Box<dyn PotentialEnergy<D>>>,
+    scratch: Mutex<Vec<DVector<D>>>,
+}
+
+impl<const D: usize> CompositePotential<D> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn term(mut self, term: impl PotentialEnergy<D> + 'static) -> Self {
+        self.terms.push(Box::new(term));
+        self
+    }
+
+    /// Puts `term` ahead of the terms added so far.
+    pub(crate) fn first_term(mut self, term: Box<dyn PotentialEnergy<D>>) -> Self {
+        self.terms.insert(0, term);
+        self
+    }
+
+    pub fn terms(&self) -> &[Box<dyn PotentialEnergy<D>>] {
+        &self.terms
+    }
+
+    /// Sums an optional quantity over the terms, `None` if any term lacks it.
+    fn sum_all<T>(&self, value: impl Fn(&dyn PotentialEnergy<D>) -> Option<T>) -> Option<Vec<T>> {
+        self.terms.iter().map(|term| value(term.as_ref())).collect()
+    }
+}
+
+fn add_tensor<const D: usize>(w: &mut VirialTensor<D>, other: &VirialTensor<D>) {
+    for (row, other_row) in w.iter_mut().zip(other.iter()) {
+        for (w, o) in row.iter_mut().zip(other_row.iter()) {
+            *w += o;
+        }
+    }
+}
+
+impl<const D: usize> PotentialEnergy<D> for CompositePotential<D> {
+    fn compute_forces(
+        &self,
+        pos: &[DVector<D>],
+        acc: &mut [DVector<D>],
+        boundaries: &dyn BoundaryConditions<D>,
+    ) {
+        d_vector::reset_array(acc);
+        let mut scratch = self.scratch.lock().unwrap();
+        scratch.resize(acc.len(), DVector::default());
+        for term in self.terms.iter() {
+            term.compute_forces(pos, &mut scratch, boundaries);
+            for (a, s) in acc.iter_mut().zip(scratch.iter()) {
+                *a += s;
+            }
+        }
+    }
+
+    fn set_types(&mut self, types: &[usize]) {
+        for term in self.terms.iter_mut() {
+            term.set_types(types);
+        }
+    }
+
+    fn set_charges(&mut self, charges: &[Real]) {
+        for term in self.terms.iter_mut() {
+            term.set_charges(charges);
+        }
+    }
+
+    fn u_sum(&self) -> Real {
+        self.terms.iter().map(|term| term.u_sum()).sum()
+    }
+
+    fn virial_sum(&self) -> Real {
+        self.terms.iter().map(|term| term.virial_sum()).sum()
+    }
+
+    /// Terms without a virial tensor are skipped if they have no virial at all.
+    fn virial_tensor(&self) -> Option<VirialTensor<D>> {
+        let mut result = [[0.; D]; D];
+        for term in self.terms.iter() {
+            match term.virial_tensor() {
+                Some(w) => add_tensor(&mut result, &w),
+                None if term.virial_sum() != 0. => return None,
+                None => {}
+            }
+        }
+        Some(result)
+    }
+
+    fn particle_energies(&self) -> Option<Vec<Real>> {
+        let energies = self.sum_all(|term| term.particle_energies())?;
+        energies.into_iter().reduce(|mut sum, e| {
+            for (s, e) in sum.iter_mut().zip(e.iter()) {
+                *s += e;
+            }
+            sum
+        })
+    }
+
+    fn particle_virials(&self) -> Option<Vec<VirialTensor<D>>> {
+        let virials = self.sum_all(|term| term.particle_virials())?;
+        virials.into_iter().reduce(|mut sum, w| {
+            for (s, w) in sum.iter_mut().zip(w.iter()) {
+                add_tensor(s, w);
+            }
+            sum
+        })
+    }
+
+    fn tail_correction(&self, n_mol: usize, volume: Real) -> Option<TailCorrection> {
+        self.terms
+            .iter()
+            .filter_map(|term| term.tail_correction(n_mol, volume))
+            .reduce(|sum, tail| TailCorrection {
+                energy: sum.energy + tail.energy,
+                pressure: sum.pressure + tail.pressure,
+            })
+    }
+
+    fn term_sums(&self) -> Option<Vec<(Real, Real)>> {
+        let sums = self
+            .terms
+            .iter()
+            .map(|term| (term.u_sum(), term.virial_sum()))
+            .collect();
+        Some(sums)
+    }
+
+    /// The list of the first term that keeps one; the rebuilds of lists kept by
+    /// later terms are not reported.
+    fn neighbors(&self) -> Option<&NeighborList> {
+        self.terms.iter().find_map(|term| term.neighbors())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        boundaries::Region,
+        ewald::Ewald,
+        initial_state,
+        job::{Job, JobSetup},
+        lennard_jones::LennardJones,
+        pair_laws::Yukawa,
+        soft_sphere::Wca,
+        species::{PairTable, Species},
+        test_support,
+    };
+
+    #[test]
+    fn terms_add_up() {
+        let (region, mut pos) = initial_state::cubic_lattice::<3>(216, 0.8);
+        test_support::jitter(&mut pos, 0.1, 1);
+        let lj: &dyn PotentialEnergy<3> = &LennardJones::new(2.5).per_particle(true);
+        let yukawa: &dyn PotentialEnergy<3> = &Yukawa::new(0.5, 2., 2.5).per_particle(true);
+        let mut acc_lj = vec![DVector::default(); pos.len()];
+        let mut acc_yukawa = vec![DVector::default(); pos.len()];
+        lj.compute_forces(&pos, &mut acc_lj, &region);
+        yukawa.compute_forces(&pos, &mut acc_yukawa, &region);
+
+        let composite = CompositePotential::new()
+            .term(LennardJones::new(2.5).per_particle(true))
+            .term(Yukawa::new(0.5, 2., 2.5).per_particle(true));
+        let mut acc = vec![DVector::default(); pos.len()];
+        composite.compute_forces(&pos, &mut acc, &region);
+        for ((a, b), c) in acc.iter().zip(acc_lj.iter()).zip(acc_yukawa.iter()) {
+            assert!((a - &(b + c)).length() < 1e-4 * (1. + a.length()));
+        }
+        let (u_lj, u_yukawa) = (lj.u_sum(), yukawa.u_sum());
+        assert!((composite.u_sum() - u_lj - u_yukawa).abs() < 1e-4 * u_lj.abs());
+        let terms = composite.term_sums().unwrap();
+        assert_eq!(
+            vec![(u_lj, lj.virial_sum()), (u_yukawa, yukawa.virial_sum())],
+            terms
+        );
+        let w = composite.virial_tensor().unwrap();
+        assert!((w[0][0] + w[1][1] + w[2][2] - composite.virial_sum()).abs() < 1e-2);
+        let energies: Real = composite.particle_energies().unwrap().iter().sum();
+        assert!((energies - composite.u_sum()).abs() < 1e-3 * u_lj.abs());
+    }
+
+    #[test]
+    fn charged_soft_spheres() {
+        let n = 4;
+        let (region, pos) = initial_state::cubic_lattice::<3>(n * n * n, 1.);
+        let types = (0..n * n * n)
+            .map(|i| (i % n + i / n % n + i / (n * n)) % 2)
+            .collect();
+        let mut job: Job<3> = JobSetup::build()
+            .boundaries(region)
+            .init_pos(pos)
+            .types(types)
+            .type_charges(&[1., -1.])
+            .random_vel(0.1)
+            .potential(
+                Wca::new().pair_table(PairTable::lorentz_berthelot(&[Species::default(); 2], 1.)),
+            )
+            .add_potential(Ewald::new(1e-5))
+            .job();
+        job.run(1);
+        let e0 = job.kinetic_energy() + job.potential_energy();
+        let terms = job.term_sums().unwrap();
+        assert_eq!(2, terms.len());
+        // Rock salt binding energy per ion pair
+        assert!((terms[1].0 / 32. + 1.747565).abs() < 2e-2);
+        job.run(200);
+        let e1 = job.kinetic_energy() + job.potential_energy();
+        assert!((e1 - e0).abs() < 1e-3 * e0.abs());
+    }
+}
@@ -0,0 +1,174 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    barostat,
+    boundaries::BoundaryConditions,
+    correlation::GreenKubo,
+    potential::PotentialEnergy,
+    prop::{PropSummary, PropTable, Props},
+    state, thermostat,
+};
+use d_vector::{DVector, Real};
+
+/// Microscopic heat flux `J = Σ_i e_i v_i + Σ_i W_i · v_i`, with `e_i` the kinetic
+/// plus per-molecule potential energy and `W_i` the per-molecule virial tensor.
+/// `None` unless the potential reports per-molecule terms.
+pub fn heat_flux<const D: usize>(
+    vel: &[DVector<D>],
+    masses: &[Real],
+    potential_energy: &dyn PotentialEnergy<D>,
+) -> Option<DVector<D>> {
+    let energies = potential_energy.particle_energies()?;
+    let virials = potential_energy.particle_virials()?;
+    if energies.len() != vel.len() || virials.len() != vel.len() {
+        return None;
+    }
+    let mut flux = [0 as Real; D];
+    for (j, ((v, u), w)) in vel
+        .iter()
+        .zip(energies.iter())
+        .zip(virials.iter())
+        .enumerate()
+    {
+        let v = v.components();
+        let e = 0.5 * state::mass(masses, j) * v.iter().map(|c| c * c).sum::<Real>() + u;
+        for (a, j) in flux.iter_mut().enumerate() {
+            *j += e * v[a] + w[a].iter().zip(v.iter()).map(|(w, v)| w * v).sum::<Real>();
+        }
+    }
+    Some(DVector::from(flux))
+}
+
+/// Thermal conductivity `κ = 1 / (D V T²) ∫ <J(0) · J(t)> dt` from the `heat_flux`,
+/// which needs a potential built with `per_particle(true)`; steps where the
+/// potential reports no per-molecule terms are skipped.
+#[derive(Debug)]
+pub struct ThermalConductivity<const D: usize>(GreenKubo);
+
+impl<const D: usize> ThermalConductivity<D> {
+    pub fn new(n_values: usize, n_origins: usize) -> Self {
+        Self(GreenKubo::new(
+            "heat_flux_acf",
+            "conductivity",
+            n_values,
+            n_origins,
+        ))
+    }
+
+    pub fn every(self, steps: usize) -> Self {
+        Self(self.0.every(steps))
+    }
+
+    pub fn step_avg(self, steps: usize) -> Self {
+        Self(self.0.step_avg(steps))
+    }
+
+    pub fn sample(&self, flux: &DVector<D>, temperature: Real, volume: Real) {
+        self.0.sample(flux.components(), temperature, volume);
+    }
+
+    /// Columns `t`, the normalized heat-flux `acf` and the running `conductivity`
+    /// integral of the current block.
+    pub fn table(&self) -> PropTable {
+        self.0.table(1., |temperature, volume| {
+            1. / (D as Real * volume * temperature * temperature)
+        })
+    }
+
+    /// Mean and standard error of the conductivity over the completed blocks.
+    pub fn conductivity(&self) -> Option<(Real, Real)> {
+        self.0.estimate()
+    }
+}
+
+impl<const D: usize> Props<D> for ThermalConductivity<D> {
+    fn reset(&self) {
+        self.0.reset();
+    }
+
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<D>,
+        pos: &[DVector<D>],
+        vel: &[DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        unwrapped: &[DVector<D>],
+        delta_t: Real,
+        time: Real,
+    ) {
+        if !self.0.step(delta_t) {
+            return;
+        }
+        if let (Some(volume), Some(flux)) =
+            (barostat::volume(boundaries), heat_flux(vel, masses, u))
+        {
+            self.sample(&flux, thermostat::temperature(vel, masses), volume);
+        }
+    }
+
+    fn accum_props(&self) {}
+
+    fn need_avg(&self, step_count: usize) -> bool {
+        self.0.need_avg(step_count)
+    }
+
+    fn avg_props(&self) {
+        self.0.avg_props(self.table());
+    }
+
+    fn summary(&self) -> Option<PropSummary> {
+        self.0.summary()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        initial_state,
+        job::{Job, JobSetup},
+        lennard_jones::LennardJones,
+        potential::NoInteraction,
+        test_support,
+    };
+
+    fn wca_job(props: impl Props<3> + 'static) -> Job<3> {
+        let (boundaries, pos) = initial_state::cubic_lattice(216, 0.8);
+        JobSetup::build()
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .random_vel(1.)
+            .potential(LennardJones::new((2 as Real).powf(1. / 6.)).per_particle(true))
+            .props(props)
+            .job()
+    }
+
+    #[test]
+    fn particle_terms_add_up() {
+        let (boundaries, pos) = initial_state::cubic_lattice::<3>(216, 0.9);
+        let mut pos = pos;
+        test_support::jitter(&mut pos, 0.1, 2);
+        let mut acc = vec![DVector::default(); pos.len()];
+        let lj = LennardJones::new((2 as Real).powf(1. / 6.)).per_particle(true);
+        PotentialEnergy::<3>::compute_forces(&lj, &pos, &mut acc, &boundaries);
+        let energies = PotentialEnergy::<3>::particle_energies(&lj).unwrap();
+        let u_sum = PotentialEnergy::<3>::u_sum(&lj);
+        assert!((energies.iter().sum::<Real>() - u_sum).abs() < 1e-3 * u_sum.abs());
+        let virials = PotentialEnergy::<3>::particle_virials(&lj).unwrap();
+        let trace: Real = virials.iter().map(|w| w[0][0] + w[1][1] + w[2][2]).sum();
+        let virial = PotentialEnergy::<3>::virial_sum(&lj);
+        assert!((trace - virial).abs() < 1e-3 * virial.abs());
+        assert!(heat_flux(&acc, &[], &NoInteraction).is_none());
+    }
+
+    #[test]
+    fn wca_fluid_conductivity() {
+        let mut job = wca_job(ThermalConductivity::new(100, 10).step_avg(500));
+        job.run(2000);
+        let summary = job.props().summary().unwrap();
+        let conductivity = summary.get("conductivity").unwrap();
+        assert!(conductivity.mean > 1. && conductivity.mean < 20.);
+        assert!(conductivity.std_dev > 0.);
+    }
+}
@@ -0,0 +1,296 @@
+#![allow(unused, dead_code)]
+
+use crate::prop::{Prop, PropEntry, PropSummary, PropTable};
+use d_vector::Real;
+use std::cell::{Cell, RefCell};
+
+/// One time origin: the data at its start and the values collected since,
+/// `count` being negative while the origin has not started yet.
+#[derive(Debug, Clone)]
+struct Origin<T> {
+    count: isize,
+    start: Vec<T>,
+    values: Vec<Real>,
+}
+
+/// `n_origins` overlapping time origins each spanning `n_values` samples, averaging
+/// some function of the data at the origin and at every later sample.
+#[derive(Debug, Clone)]
+pub struct Origins<T> {
+    n_values: usize,
+    origins: Vec<Origin<T>>,
+    sum: Vec<Real>,
+    full: usize,
+}
+
+impl<T: Clone> Origins<T> {
+    pub fn new(n_values: usize, n_origins: usize) -> Self {
+        let n_values = n_values.max(1);
+        let n_origins = n_origins.max(1);
+        let origins = (0..n_origins)
+            .map(|k| Origin {
+                count: -((k * n_values / n_origins) as isize),
+                start: Vec::new(),
+                values: vec![0.; n_values],
+            })
+            .collect();
+        Self {
+            n_values,
+            origins,
+            sum: vec![0.; n_values],
+            full: 0,
+        }
+    }
+
+    pub fn n_values(&self) -> usize {
+        self.n_values
+    }
+
+    /// Adds `value(start, data)` at the current lag of every running origin.
+    pub fn sample(&mut self, data: &[T], value: impl Fn(&[T], &[T]) -> Real) {
+        for origin in self.origins.iter_mut() {
+            if origin.count == 0 {
+                origin.start.clear();
+                origin.start.extend_from_slice(data);
+            }
+            if origin.count >= 0 {
+                origin.values[origin.count as usize] = value(&origin.start, data);
+            }
+            origin.count += 1;
+            if origin.count as usize == self.n_values {
+                for (s, v) in self.sum.iter_mut().zip(origin.values.iter()) {
+                    *s += v;
+                }
+                self.full += 1;
+                origin.count = 0;
+            }
+        }
+    }
+
+    /// Number of completed origins contributing to `values`.
+    pub fn full(&self) -> usize {
+        self.full
+    }
+
+    pub fn values(&self) -> Vec<Real> {
+        let full = self.full.max(1) as Real;
+        self.sum.iter().map(|s| s / full).collect()
+    }
+
+    /// Clears the averages; origins in progress carry on.
+    pub fn reset(&mut self) {
+        self.sum.iter_mut().for_each(|s| *s = 0.);
+        self.full = 0;
+    }
+}
+
+/// Time correlation `<a(t0) · a(t0 + t)>` of a sampled data vector,
+/// measured from `n_origins` overlapping origins each spanning `n_values` samples.
+#[derive(Debug, Clone)]
+pub struct TimeCorrelation(Origins<Real>);
+
+impl TimeCorrelation {
+    pub fn new(n_values: usize, n_origins: usize) -> Self {
+        Self(Origins::new(n_values, n_origins))
+    }
+
+    pub fn n_values(&self) -> usize {
+        self.0.n_values()
+    }
+
+    pub fn sample(&mut self, data: &[Real]) {
+        self.0.sample(data, |start, data| {
+            start.iter().zip(data.iter()).map(|(a, b)| a * b).sum()
+        });
+    }
+
+    /// Number of completed origins contributing to `values`.
+    pub fn full(&self) -> usize {
+        self.0.full()
+    }
+
+    pub fn values(&self) -> Vec<Real> {
+        self.0.values()
+    }
+
+    /// Clears the averages; origins in progress carry on.
+    pub fn reset(&mut self) {
+        self.0.reset();
+    }
+}
+
+/// Running trapezoidal integral of `values` spaced `dt` apart.
+pub fn running_integral(values: &[Real], dt: Real) -> Vec<Real> {
+    let mut total = 0.;
+    let mut result = Vec::with_capacity(values.len());
+    for (k, v) in values.iter().enumerate() {
+        if k > 0 {
+            total += 0.5 * dt * (values[k - 1] + v);
+        }
+        result.push(total);
+    }
+    result
+}
+
+/// Mean and standard error of independent block estimates.
+pub fn block_mean(blocks: &[Real]) -> Option<(Real, Real)> {
+    if blocks.is_empty() {
+        return None;
+    }
+    let n = blocks.len() as Real;
+    let mean = blocks.iter().sum::<Real>() / n;
+    let error = if blocks.len() > 1 {
+        let var = blocks.iter().map(|b| (b - mean) * (b - mean)).sum::<Real>() / (n - 1.);
+        (var / n).sqrt()
+    } else {
+        0.
+    };
+    Some((mean, error))
+}
+
+/// Green–Kubo transport coefficient `factor(T, V) ∫ <a(0) · a(t)> dt / norm` of a
+/// flux sampled every `every` steps. Each `step_avg` steps the running integral at
+/// the longest lag is kept as one block estimate, reported as `quantity` with the
+/// standard error over the blocks.
+#[derive(Debug)]
+pub struct GreenKubo {
+    name: &'static str,
+    quantity: &'static str,
+    every: usize,
+    step_avg: usize,
+    delta_t: Cell<Real>,
+    steps: Cell<usize>,
+    step_count: Cell<usize>,
+    corr: RefCell<TimeCorrelation>,
+    temperature: RefCell<Prop>,
+    volume: RefCell<Prop>,
+    blocks: RefCell<Vec<Real>>,
+    last: RefCell<Option<PropSummary>>,
+}
+
+impl GreenKubo {
+    pub fn new(
+        name: &'static str,
+        quantity: &'static str,
+        n_values: usize,
+        n_origins: usize,
+    ) -> Self {
+        Self {
+            name,
+            quantity,
+            every: 1,
+            step_avg: 1000,
+            delta_t: Cell::new(0.),
+            steps: Cell::new(0),
+            step_count: Cell::new(0),
+            corr: RefCell::new(TimeCorrelation::new(n_values, n_origins)),
+            temperature: RefCell::default(),
+            volume: RefCell::default(),
+            blocks: RefCell::new(Vec::new()),
+            last: RefCell::new(None),
+        }
+    }
+
+    pub fn every(mut self, steps: usize) -> Self {
+        self.every = steps.max(1);
+        self
+    }
+
+    pub fn step_avg(mut self, steps: usize) -> Self {
+        self.step_avg = steps.max(1);
+        self
+    }
+
+    /// Counts one job step of length `delta_t`; true when the flux is due.
+    pub fn step(&self, delta_t: Real) -> bool {
+        self.delta_t.set(delta_t);
+        self.steps.set(self.steps.get() + 1);
+        self.steps.get().is_multiple_of(self.every)
+    }
+
+    pub fn sample(&self, flux: &[Real], temperature: Real, volume: Real) {
+        self.corr.borrow_mut().sample(flux);
+        for (prop, val) in [(&self.temperature, temperature), (&self.volume, volume)] {
+            let mut p = prop.borrow_mut();
+            p.val = val;
+            p.accum();
+        }
+    }
+
+    /// Columns `t`, the normalized `acf` and the running integral of the current
+    /// block, with the correlation divided by `norm` and the integral scaled by
+    /// `factor` of the mean temperature and volume.
+    pub fn table(&self, norm: Real, factor: impl Fn(Real, Real) -> Real) -> PropTable {
+        let mut table = PropTable::new(self.name, &["t", "acf", self.quantity]);
+        let corr = self.corr.borrow();
+        let temperature = self.temperature.borrow().mean();
+        if corr.full() == 0 || temperature <= 0. {
+            return table;
+        }
+        let lag = self.every as Real * self.delta_t.get();
+        let values: Vec<_> = corr.values().iter().map(|c| c / norm).collect();
+        let factor = factor(temperature, self.volume.borrow().mean());
+        let integral = running_integral(&values, lag);
+        for (k, (c, i)) in values.iter().zip(integral.iter()).enumerate() {
+            let normalized = if values[0] > 0. { c / values[0] } else { 0. };
+            table
+                .rows
+                .push(vec![k as Real * lag, normalized, factor * i]);
+        }
+        table
+    }
+
+    /// Mean and standard error over the completed blocks.
+    pub fn estimate(&self) -> Option<(Real, Real)> {
+        block_mean(&self.blocks.borrow())
+    }
+
+    pub fn reset(&self) {
+        self.corr.borrow_mut().reset();
+        for prop in [&self.temperature, &self.volume] {
+            prop.borrow_mut().reset();
+        }
+    }
+
+    pub fn need_avg(&self, step_count: usize) -> bool {
+        self.step_count.set(step_count);
+        step_count.is_multiple_of(self.step_avg)
+    }
+
+    /// Closes the block whose `table` is given.
+    pub fn avg_props(&self, table: PropTable) {
+        if let Some(value) = table.column(self.quantity).and_then(|c| c.last().copied()) {
+            self.blocks.borrow_mut().push(value);
+        }
+        let entries = self
+            .estimate()
+            .map(|(mean, error)| PropEntry::new(self.quantity, mean, error))
+            .into_iter()
+            .collect();
+        *self.last.borrow_mut() = Some(PropSummary {
+            step_count: self.step_count.get(),
+            entries,
+            tables: vec![table],
+        });
+    }
+
+    pub fn summary(&self) -> Option<PropSummary> {
+        self.last.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constant_signal() {
+        let mut corr = TimeCorrelation::new(4, 2);
+        for _ in 0..20 {
+            corr.sample(&[1., 2.]);
+        }
+        assert!(corr.full() > 0);
+        assert_eq!(vec![5.; 4], corr.values());
+        assert_eq!(vec![0., 5., 10., 15.], running_integral(&corr.values(), 1.));
+    }
+}
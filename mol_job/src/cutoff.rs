@@ -0,0 +1,164 @@
+#![allow(unused, dead_code)]
+
+use crate::rdf;
+use d_vector::Real;
+
+/// How a pair law is brought to zero at the cutoff `r_c`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Cutoff {
+    /// `u(r)` as is, jumping to zero at `r_c`.
+    #[default]
+    Truncated,
+    /// `u(r) - u(r_c)`, continuous energy.
+    Shifted,
+    /// `u(r) + offset`, the force of the truncated law. `Offset(1.)` is the
+    /// default of `LennardJones`.
+    Offset(Real),
+    /// `u(r) - u(r_c) + (r - r_c) F(r_c)`, continuous energy and force.
+    ShiftedForce,
+    /// CHARMM switching function `S(r) u(r)` between `from · r_c` and `r_c`.
+    Switched { from: Real },
+}
+
+impl Cutoff {
+    /// Energy and `F / r` at squared distance `rr < r_cut²`, given the
+    /// unmodified law as a function of the squared distance.
+    pub fn apply(&self, rr: Real, r_cut: Real, law: impl Fn(Real) -> (Real, Real)) -> (Real, Real) {
+        let (u, f) = law(rr);
+        match *self {
+            Self::Truncated => (u, f),
+            Self::Shifted => (u - law(r_cut * r_cut).0, f),
+            Self::Offset(offset) => (u + offset, f),
+            Self::ShiftedForce => {
+                let (u_c, f_c) = law(r_cut * r_cut);
+                let r = rr.sqrt();
+                (u - u_c + (r - r_cut) * f_c * r_cut, f - f_c * r_cut / r)
+            }
+            Self::Switched { from } => {
+                let (a, b) = (r_cut * r_cut, from * from * r_cut * r_cut);
+                if rr <= b {
+                    return (u, f);
+                }
+                let norm = (a - b).powi(3);
+                let s = (a - rr) * (a - rr) * (a + 2. * rr - 3. * b) / norm;
+                (s * u, s * f + 12. * u * (a - rr) * (rr - b) / norm)
+            }
+        }
+    }
+
+    /// Constant subtracted from every pair energy inside the cutoff, given
+    /// `u(r_c)`; `None` when the law is changed by more than a constant.
+    pub fn energy_shift(&self, u_cut: Real) -> Option<Real> {
+        match *self {
+            Self::Truncated => Some(0.),
+            Self::Shifted => Some(u_cut),
+            Self::Offset(offset) => Some(-offset),
+            Self::ShiftedForce | Self::Switched { .. } => None,
+        }
+    }
+}
+
+/// Long-range corrections for the interactions a cutoff leaves out, assuming
+/// `g(r) = 1` beyond it: total energy and pressure.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TailCorrection {
+    pub energy: Real,
+    pub pressure: Real,
+}
+
+/// `S_D ∫ r^(D-1) c r^(-n) dr` from `r_cut` to infinity, and the matching virial
+/// integral `S_D ∫ r^(D-1) (-r du/dr) dr`, for `n > D`.
+pub fn power_tail(c: Real, n: Real, r_cut: Real, dim: usize) -> (Real, Real) {
+    let surface = dim as Real * rdf::unit_ball_volume(dim);
+    let u = surface * c * r_cut.powf(dim as Real - n) / (n - dim as Real);
+    (u, n * u)
+}
+
+/// Sums the tail integrals of each pair of types over the composition of `types`:
+/// `U = N ρ / 2 Σ x_i x_j I_u` and `P = ρ² / (2 D) Σ x_i x_j I_w`.
+pub fn tail_correction(
+    n_types: usize,
+    types: &[usize],
+    n_mol: usize,
+    volume: Real,
+    dim: usize,
+    integrals: impl Fn(usize, usize) -> (Real, Real),
+) -> TailCorrection {
+    let mut fractions = vec![0.; n_types];
+    if types.is_empty() {
+        fractions[0] = 1.;
+    } else {
+        for t in types.iter() {
+            fractions[*t] += 1. / types.len() as Real;
+        }
+    }
+    let (mut i_u, mut i_w) = (0., 0.);
+    for (i, x_i) in fractions.iter().enumerate() {
+        for (j, x_j) in fractions.iter().enumerate() {
+            let (u, w) = integrals(i, j);
+            i_u += x_i * x_j * u;
+            i_w += x_i * x_j * w;
+        }
+    }
+    let density = n_mol as Real / volume;
+    TailCorrection {
+        energy: 0.5 * n_mol as Real * density * i_u,
+        pressure: 0.5 * density * density * i_w / dim as Real,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lj(rr: Real) -> (Real, Real) {
+        let rri3 = 1. / (rr * rr * rr);
+        (4. * rri3 * (rri3 - 1.), 48. * rri3 * (rri3 - 0.5) / rr)
+    }
+
+    #[test]
+    fn cutoff_variants() {
+        let r_cut = 2.5;
+        let just_inside = (r_cut - 1e-3) * (r_cut - 1e-3);
+        let (u, _) = Cutoff::Shifted.apply(just_inside, r_cut, lj);
+        assert!(u.abs() < 1e-4);
+        let (u, f) = Cutoff::ShiftedForce.apply(just_inside, r_cut, lj);
+        assert!(u.abs() < 1e-6 && f.abs() < 1e-4);
+        let switched = Cutoff::Switched { from: 0.8 };
+        let (u, f) = switched.apply(just_inside, r_cut, lj);
+        assert!(u.abs() < 1e-6 && f.abs() < 1e-3);
+        assert_eq!(lj(1.9 * 1.9), switched.apply(1.9 * 1.9, r_cut, lj));
+        let (u, f) = lj(1.1 * 1.1);
+        assert_eq!((u + 1., f), Cutoff::Offset(1.).apply(1.1 * 1.1, r_cut, lj));
+        assert_eq!(Some(-1.), Cutoff::Offset(1.).energy_shift(0.5));
+        assert_eq!(None, switched.energy_shift(0.5));
+        for cutoff in [Cutoff::Shifted, Cutoff::ShiftedForce, switched] {
+            for r in [1.1, 1.5, 2.1, 2.3] {
+                let h = 1e-3;
+                let u = |r: Real| cutoff.apply(r * r, r_cut, lj).0;
+                let force = -(u(r + h) - u(r - h)) / (2. * h);
+                let f = cutoff.apply(r * r, r_cut, lj).1;
+                assert!((f * r - force).abs() < 1e-2 * (1. + force.abs()));
+            }
+        }
+    }
+
+    #[test]
+    fn lennard_jones_tail() {
+        // Standard 3D results for σ = ε = 1
+        let r_cut: Real = 2.5;
+        let (n_mol, volume) = (1000, 1250.);
+        let density = 0.8;
+        let tail = tail_correction(1, &[], n_mol, volume, 3, |_, _| {
+            let (u12, w12) = power_tail(4., 12., r_cut, 3);
+            let (u6, w6) = power_tail(-4., 6., r_cut, 3);
+            (u12 + u6, w12 + w6)
+        });
+        let pi = std::f32::consts::PI;
+        let sr3 = r_cut.powi(-3);
+        let energy = 8. / 3. * pi * density * (sr3 * sr3 * sr3 / 3. - sr3);
+        let pressure = 16. / 3. * pi * density * density * (2. / 3. * sr3 * sr3 * sr3 - sr3);
+        assert!((tail.energy / n_mol as Real - energy).abs() < 1e-4);
+        assert!((tail.pressure - pressure).abs() < 1e-4);
+    }
+}
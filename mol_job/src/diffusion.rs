@@ -0,0 +1,362 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    boundaries::BoundaryConditions,
+    correlation::{self, Origins, TimeCorrelation},
+    potential::PotentialEnergy,
+    prop::{PropEntry, PropSummary, PropTable, Props},
+};
+use d_vector::{DVector, Real};
+use std::cell::{Cell, RefCell};
+
+/// Least-squares slope and intercept of `y` against `x`.
+pub fn fit_line(x: &[Real], y: &[Real]) -> Option<(Real, Real)> {
+    let n = x.len().min(y.len()) as Real;
+    if n < 2. {
+        return None;
+    }
+    let (sx, sy) = (x.iter().sum::<Real>(), y.iter().sum::<Real>());
+    let sxx = x.iter().map(|x| x * x).sum::<Real>();
+    let sxy = x.iter().zip(y.iter()).map(|(x, y)| x * y).sum::<Real>();
+    let det = n * sxx - sx * sx;
+    if det == 0. {
+        return None;
+    }
+    let slope = (n * sxy - sx * sy) / det;
+    Some((slope, (sy - slope * sx) / n))
+}
+
+/// Mean-square displacement of unwrapped positions against the time lag,
+/// averaged over `n_origins` staggered time origins per `n_values` window.
+/// The diffusion coefficient comes from the Einstein relation
+/// `MSD = 2 D_s t + c`, fitted over `fit_window`.
+#[derive(Debug)]
+pub struct MeanSquareDisplacement<const D: usize> {
+    every: usize,
+    delta_t: Cell<Real>,
+    fit_window: (Real, Real),
+    step_avg: usize,
+    steps: Cell<usize>,
+    step_count: Cell<usize>,
+    origins: RefCell<Origins<DVector<D>>>,
+    last: RefCell<Option<PropSummary>>,
+}
+
+impl<const D: usize> MeanSquareDisplacement<D> {
+    pub fn new(n_values: usize, n_origins: usize) -> Self {
+        Self {
+            every: 1,
+            delta_t: Cell::new(0.),
+            fit_window: (0., Real::MAX),
+            step_avg: 1000,
+            steps: Cell::new(0),
+            step_count: Cell::new(0),
+            origins: RefCell::new(Origins::new(n_values.max(2), n_origins)),
+            last: RefCell::new(None),
+        }
+    }
+
+    pub fn every(mut self, steps: usize) -> Self {
+        self.every = steps.max(1);
+        self
+    }
+
+    pub fn fit_window(mut self, t_min: Real, t_max: Real) -> Self {
+        self.fit_window = (t_min, t_max);
+        self
+    }
+
+    pub fn step_avg(mut self, steps: usize) -> Self {
+        self.step_avg = steps.max(1);
+        self
+    }
+
+    /// Adds one configuration of unwrapped positions.
+    pub fn sample(&self, unwrapped: &[DVector<D>]) {
+        let n_mol = unwrapped.len().max(1) as Real;
+        self.origins.borrow_mut().sample(unwrapped, |start, now| {
+            now.iter()
+                .zip(start.iter())
+                .map(|(r, r0)| (r - r0).square_length())
+                .sum::<Real>()
+                / n_mol
+        });
+    }
+
+    /// Columns `t` and `msd`, averaged over the completed origins.
+    pub fn table(&self) -> PropTable {
+        let mut table = PropTable::new("msd", &["t", "msd"]);
+        let origins = self.origins.borrow();
+        if origins.full() == 0 {
+            return table;
+        }
+        let lag = self.every as Real * self.delta_t.get();
+        for (k, msd) in origins.values().iter().enumerate() {
+            table.rows.push(vec![k as Real * lag, *msd]);
+        }
+        table
+    }
+
+    pub fn diffusion(&self) -> Option<Real> {
+        let table = self.table();
+        let (t_min, t_max) = self.fit_window;
+        let (t, msd): (Vec<_>, Vec<_>) = table
+            .rows
+            .iter()
+            .filter(|row| row[0] >= t_min && row[0] <= t_max)
+            .map(|row| (row[0], row[1]))
+            .unzip();
+        fit_line(&t, &msd).map(|(slope, _)| slope / (2. * D as Real))
+    }
+}
+
+impl<const D: usize> Props<D> for MeanSquareDisplacement<D> {
+    fn reset(&self) {
+        self.origins.borrow_mut().reset();
+    }
+
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<D>,
+        pos: &[DVector<D>],
+        vel: &[DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        unwrapped: &[DVector<D>],
+        delta_t: Real,
+        time: Real,
+    ) {
+        self.delta_t.set(delta_t);
+        self.steps.set(self.steps.get() + 1);
+        if self.steps.get().is_multiple_of(self.every) {
+            self.sample(unwrapped);
+        }
+    }
+
+    fn accum_props(&self) {}
+
+    fn need_avg(&self, step_count: usize) -> bool {
+        self.step_count.set(step_count);
+        step_count.is_multiple_of(self.step_avg)
+    }
+
+    fn avg_props(&self) {
+        let entries = self
+            .diffusion()
+            .map(|d| PropEntry::new("diffusion", d, 0.))
+            .into_iter()
+            .collect();
+        *self.last.borrow_mut() = Some(PropSummary {
+            step_count: self.step_count.get(),
+            entries,
+            tables: vec![self.table()],
+        });
+    }
+
+    fn summary(&self) -> Option<PropSummary> {
+        self.last.borrow().clone()
+    }
+}
+
+/// Velocity autocorrelation function sampled every `every` steps over
+/// `n_values` lags, and the Green–Kubo diffusion coefficient
+/// `D_s = 1/D ∫ <v(0) · v(t)> dt` integrated from it.
+#[derive(Debug)]
+pub struct VelocityAutocorrelation<const D: usize> {
+    every: usize,
+    delta_t: Cell<Real>,
+    step_avg: usize,
+    steps: Cell<usize>,
+    step_count: Cell<usize>,
+    n_mol: Cell<usize>,
+    corr: RefCell<TimeCorrelation>,
+    last: RefCell<Option<PropSummary>>,
+}
+
+impl<const D: usize> VelocityAutocorrelation<D> {
+    pub fn new(n_values: usize, n_origins: usize) -> Self {
+        Self {
+            every: 1,
+            delta_t: Cell::new(0.),
+            step_avg: 1000,
+            steps: Cell::new(0),
+            step_count: Cell::new(0),
+            n_mol: Cell::new(0),
+            corr: RefCell::new(TimeCorrelation::new(n_values, n_origins)),
+            last: RefCell::new(None),
+        }
+    }
+
+    pub fn every(mut self, steps: usize) -> Self {
+        self.every = steps.max(1);
+        self
+    }
+
+    pub fn step_avg(mut self, steps: usize) -> Self {
+        self.step_avg = steps.max(1);
+        self
+    }
+
+    pub fn sample(&self, vel: &[DVector<D>]) {
+        let data: Vec<Real> = vel.iter().flat_map(|v| *v.components()).collect();
+        self.n_mol.set(vel.len());
+        self.corr.borrow_mut().sample(&data);
+    }
+
+    /// Columns `t`, the normalized `vacf` and the running Green–Kubo `diffusion`.
+    pub fn table(&self) -> PropTable {
+        let mut table = PropTable::new("vacf", &["t", "vacf", "diffusion"]);
+        let corr = self.corr.borrow();
+        if corr.full() == 0 {
+            return table;
+        }
+        let lag = self.every as Real * self.delta_t.get();
+        let norm = (self.n_mol.get().max(1) * D) as Real;
+        let values: Vec<_> = corr.values().iter().map(|c| c / norm).collect();
+        let integral = correlation::running_integral(&values, lag);
+        for (k, (c, d)) in values.iter().zip(integral.iter()).enumerate() {
+            let normalized = if values[0] > 0. { c / values[0] } else { 0. };
+            table.rows.push(vec![k as Real * lag, normalized, *d]);
+        }
+        table
+    }
+
+    pub fn diffusion(&self) -> Option<Real> {
+        self.table().column("diffusion")?.last().copied()
+    }
+}
+
+impl<const D: usize> Props<D> for VelocityAutocorrelation<D> {
+    fn reset(&self) {
+        self.corr.borrow_mut().reset();
+    }
+
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<D>,
+        pos: &[DVector<D>],
+        vel: &[DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        unwrapped: &[DVector<D>],
+        delta_t: Real,
+        time: Real,
+    ) {
+        self.delta_t.set(delta_t);
+        self.steps.set(self.steps.get() + 1);
+        if self.steps.get().is_multiple_of(self.every) {
+            self.sample(vel);
+        }
+    }
+
+    fn accum_props(&self) {}
+
+    fn need_avg(&self, step_count: usize) -> bool {
+        self.step_count.set(step_count);
+        step_count.is_multiple_of(self.step_avg)
+    }
+
+    fn avg_props(&self) {
+        let entries = self
+            .diffusion()
+            .map(|d| PropEntry::new("diffusion", d, 0.))
+            .into_iter()
+            .collect();
+        *self.last.borrow_mut() = Some(PropSummary {
+            step_count: self.step_count.get(),
+            entries,
+            tables: vec![self.table()],
+        });
+    }
+
+    fn summary(&self) -> Option<PropSummary> {
+        self.last.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        boundaries::{Images, Region},
+        integrator::Integrator,
+        job::{Job, JobSetup},
+        langevin::{Brownian, Langevin},
+        potential::NoInteraction,
+        test_support,
+    };
+
+    #[test]
+    fn line_fit() {
+        let (slope, intercept) = fit_line(&[0., 1., 2.], &[1., 3., 5.]).unwrap();
+        assert!((slope - 2.).abs() < 1e-6);
+        assert!((intercept - 1.).abs() < 1e-6);
+        assert_eq!(None, fit_line(&[1.], &[1.]));
+    }
+
+    #[test]
+    fn brownian_einstein_diffusion() {
+        let pos = test_support::random_pos(500, 10., 3);
+        let mut job: Job<3> = JobSetup::build()
+            .delta_t(0.01)
+            .boundaries(Region::new([10.; 3]))
+            .init_pos(pos)
+            .potential(NoInteraction)
+            .integrator(Brownian::new(1., 2.).seed(5))
+            .props(
+                MeanSquareDisplacement::new(100, 10)
+                    .fit_window(0.2, 1.)
+                    .step_avg(2000),
+            )
+            .job();
+        job.run(2000);
+        let summary = job.props().summary().unwrap();
+        let diffusion = summary.get("diffusion").unwrap().mean;
+        assert!((diffusion - 0.5).abs() < 0.05);
+        assert!(job.images().iter().any(|image| image != &[0; 3]));
+    }
+
+    #[test]
+    fn green_kubo_matches_einstein() {
+        let region = Region::new([10.; 3]);
+        let langevin = Langevin::new(1., 2.).seed(9);
+        let msd = MeanSquareDisplacement::new(200, 10).fit_window(1., 2.);
+        let vacf = VelocityAutocorrelation::new(200, 10);
+        let mut images = Images::default();
+        let mut pos: Vec<DVector<3>> = test_support::random_pos(500, 10., 4);
+        let mut vel = vec![DVector::default(); 500];
+        let mut acc = vec![DVector::default(); 500];
+        for step in 1..=4000 {
+            langevin.single_step(
+                0.01,
+                &mut pos,
+                &mut vel,
+                &mut acc,
+                &[],
+                &region,
+                &NoInteraction,
+            );
+            images.track(&pos, &region);
+            for props in [&msd as &dyn Props<3>, &vacf] {
+                let unwrapped = images.unwrapped();
+                let time = step as Real * 0.01;
+                props.eval_props(
+                    &NoInteraction,
+                    &pos,
+                    &vel,
+                    &[],
+                    &region,
+                    unwrapped,
+                    0.01,
+                    time,
+                );
+            }
+        }
+        let vacf_table = vacf.table();
+        assert!((vacf_table.column("vacf").unwrap()[0] - 1.).abs() < 1e-6);
+        let green_kubo = vacf.diffusion().unwrap();
+        let einstein = msd.diffusion().unwrap();
+        assert!((green_kubo - 0.5).abs() < 0.05);
+        assert!((green_kubo - einstein).abs() < 0.05);
+    }
+}
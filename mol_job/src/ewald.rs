@@ -0,0 +1,680 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    boundaries::BoundaryConditions,
+    cells::{PairSearch, Pairs},
+    forces::{self, PairSums, PairTerm, StoredSums},
+    potential::{PotentialEnergy, VirialTensor},
+};
+use d_vector::{reset_array, DVector, Real};
+use num_complex::Complex64;
+use rustfft::{FftDirection, FftPlanner};
+use std::{
+    f64::consts::PI,
+    fmt::{self, Debug},
+    sync::Mutex,
+};
+
+/// Splitting parameter, cutoffs and mesh size of an Ewald sum.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EwaldParameters {
+    pub alpha: Real,
+    pub r_cut: Real,
+    /// Reciprocal vectors `2π (n_x / L_x, n_y / L_y, n_z / L_z)` are summed up to this length.
+    pub k_cut: Real,
+    pub k_max: [usize; 3],
+    /// PME mesh points along each axis, at least `3 k_max` so that the aliasing of the
+    /// B-spline interpolation stays below the truncation error.
+    pub mesh: [usize; 3],
+}
+
+impl EwaldParameters {
+    /// Truncates both parts at a relative error near `accuracy`, `exp(-s²) = accuracy`
+    /// with `s = α r_c = k_c / 2α`. Without a given `r_cut`, `α = √π (N / V²)^(1/6)`
+    /// balances the work of both parts, as long as `r_c` fits in half the box.
+    pub fn choose(
+        accuracy: Real,
+        n_mol: usize,
+        box_size: &[Real; 3],
+        r_cut: Option<Real>,
+        order: usize,
+    ) -> Self {
+        let s = (-accuracy.ln()).sqrt();
+        let half_box = 0.5 * box_size.iter().fold(Real::MAX, |a, b| a.min(*b));
+        let r_cut = r_cut
+            .unwrap_or_else(|| {
+                let volume: Real = box_size.iter().product();
+                let alpha =
+                    std::f32::consts::PI.sqrt() * (n_mol as Real / (volume * volume)).powf(1. / 6.);
+                s / alpha
+            })
+            .min(half_box);
+        let alpha = s / r_cut;
+        let k_cut = 2. * alpha * s;
+        let k_max = box_size.map(|l| (k_cut * l / (2. * std::f32::consts::PI)).ceil() as usize);
+        Self {
+            alpha,
+            r_cut,
+            k_cut,
+            k_max,
+            mesh: k_max.map(|k| fft_size((3 * k).max(order))),
+        }
+    }
+}
+
+/// Smallest `2^a 3^b 5^c` not below `n`.
+fn fft_size(n: usize) -> usize {
+    (n..)
+        .find(|m| {
+            let mut m = *m;
+            for p in [2, 3, 5] {
+                while m.is_multiple_of(p) {
+                    m /= p;
+                }
+            }
+            m == 1
+        })
+        .unwrap()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Reciprocal {
+    Sum,
+    Mesh { order: usize },
+}
+
+/// Coulomb energy `k q_i q_j / r` summed over all periodic images in three dimensions,
+/// split into a screened real-space pair sum and a smooth reciprocal-space part.
+#[derive(Debug)]
+pub struct Ewald {
+    accuracy: Real,
+    r_cut: Option<Real>,
+    prefactor: Real,
+    reciprocal: Reciprocal,
+    charges: Vec<Real>,
+    search: PairSearch,
+    sums: StoredSums,
+    plans: Plans,
+}
+
+/// FFT planner of the PME mesh, which keeps every plan it has made between calls.
+struct Plans(Mutex<FftPlanner<f64>>);
+
+impl Default for Plans {
+    fn default() -> Self {
+        Self(Mutex::new(FftPlanner::new()))
+    }
+}
+
+impl Debug for Plans {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Plans").finish_non_exhaustive()
+    }
+}
+
+impl Ewald {
+    /// Classic Ewald summation over reciprocal vectors, `O(N^(3/2))` at best.
+    pub fn new(accuracy: Real) -> Self {
+        Self {
+            accuracy,
+            r_cut: None,
+            prefactor: 1.,
+            reciprocal: Reciprocal::Sum,
+            charges: Vec::new(),
+            search: PairSearch::default(),
+            sums: StoredSums::default(),
+            plans: Plans::default(),
+        }
+    }
+
+    /// Smooth particle-mesh Ewald, spreading charges on an FFT mesh with
+    /// B-splines of order 6, `O(N log N)`.
+    pub fn pme(accuracy: Real) -> Self {
+        Self {
+            reciprocal: Reciprocal::Mesh { order: 6 },
+            ..Self::new(accuracy)
+        }
+    }
+
+    /// B-spline order of the PME charge assignment.
+    pub fn order(mut self, order: usize) -> Self {
+        assert!(order >= 3);
+        if let Reciprocal::Mesh { .. } = self.reciprocal {
+            self.reciprocal = Reciprocal::Mesh { order };
+        }
+        self
+    }
+
+    /// Real-space cutoff, otherwise chosen from the density.
+    pub fn r_cut(mut self, r_cut: Real) -> Self {
+        self.r_cut = Some(r_cut);
+        self
+    }
+
+    /// Coulomb constant `k = 1 / 4πε`, 1 by default.
+    pub fn prefactor(mut self, prefactor: Real) -> Self {
+        self.prefactor = prefactor;
+        self
+    }
+
+    pub fn search(mut self, search: PairSearch) -> Self {
+        self.search = search;
+        self
+    }
+
+    pub fn parameters(&self, n_mol: usize, box_size: &[Real; 3]) -> EwaldParameters {
+        let order = match self.reciprocal {
+            Reciprocal::Sum => 1,
+            Reciprocal::Mesh { order } => order,
+        };
+        EwaldParameters::choose(self.accuracy, n_mol, box_size, self.r_cut, order)
+    }
+}
+
+impl PotentialEnergy<3> for Ewald {
+    fn compute_forces(
+        &self,
+        pos: &[DVector<3>],
+        acc: &mut [DVector<3>],
+        boundaries: &dyn BoundaryConditions<3>,
+    ) {
+        let n_mol = pos.len();
+        assert_eq!(n_mol, acc.len());
+        assert!(self.charges.is_empty() || self.charges.len() == n_mol);
+        let box_size = *boundaries
+            .box_size()
+            .expect("Ewald sums need a periodic box");
+        let params = self.parameters(n_mol, &box_size);
+        let charge = |j: usize| self.charges.get(j).copied().unwrap_or_default();
+        reset_array(acc);
+
+        let (alpha, r_cut) = (params.alpha, params.r_cut);
+        let pairs = Pairs::new(pos, boundaries, r_cut, self.search);
+        let mut sums = forces::accumulate(&pairs, acc, None, |j1, j2| {
+            let qq = self.prefactor * charge(j1) * charge(j2);
+            let mut dr = &pos[j1] - &pos[j2];
+            boundaries.wrap(&mut dr);
+            let rr = dr.square_length();
+            if qq == 0. || rr >= r_cut * r_cut {
+                return None;
+            }
+            let r = rr.sqrt();
+            let u = qq * erfc(alpha * r) / r;
+            let gauss = 2. * alpha / std::f32::consts::PI.sqrt() * (-alpha * alpha * rr).exp();
+            let force_value = (u + qq * gauss) / rr;
+            Some(PairTerm {
+                force: force_value * &dr,
+                dr,
+                u,
+                virial: force_value * rr,
+            })
+        });
+
+        let charges: Vec<f64> = (0..n_mol).map(|j| charge(j) as f64).collect();
+        let mut long_range = vec![[0.; 3]; n_mol];
+        let (u_k, mut w_k) = match self.reciprocal {
+            Reciprocal::Sum => reciprocal_sum(pos, &charges, &box_size, &params, &mut long_range),
+            Reciprocal::Mesh { order } => {
+                let mut planner = self.plans.0.lock().unwrap();
+                mesh_sum(
+                    pos,
+                    &charges,
+                    &box_size,
+                    &params,
+                    order,
+                    &mut planner,
+                    &mut long_range,
+                )
+            }
+        };
+        // Self interaction of the screening charges and the neutralizing background
+        let alpha = alpha as f64;
+        let volume: f64 = box_size.iter().map(|l| *l as f64).product();
+        let q_sum: f64 = charges.iter().sum();
+        let u_self = -alpha / PI.sqrt() * charges.iter().map(|q| q * q).sum::<f64>();
+        let u_background = -PI * q_sum * q_sum / (2. * volume * alpha * alpha);
+        for (a, row) in w_k.iter_mut().enumerate() {
+            row[a] += u_background;
+        }
+
+        let k = self.prefactor as f64;
+        for (a, f) in acc.iter_mut().zip(long_range.iter()) {
+            *a += &DVector::from(f.map(|f| (k * f) as Real));
+        }
+        let long_sums = PairSums::<3> {
+            u: (k * (u_k + u_self + u_background)) as Real,
+            virial: (k * (w_k[0][0] + w_k[1][1] + w_k[2][2])) as Real,
+            virial_tensor: w_k.map(|row| row.map(|w| (k * w) as Real)),
+        };
+        sums.add(&long_sums);
+        self.sums.store(&sums);
+    }
+
+    fn set_charges(&mut self, charges: &[Real]) {
+        self.charges = charges.to_vec();
+    }
+
+    fn u_sum(&self) -> Real {
+        self.sums.u_sum()
+    }
+
+    fn virial_sum(&self) -> Real {
+        self.sums.virial_sum()
+    }
+
+    fn virial_tensor(&self) -> Option<VirialTensor<3>> {
+        self.sums.virial_tensor()
+    }
+}
+
+/// Complementary error function with a fractional error below `1.2e-7`
+/// (Numerical Recipes' Chebyshev fit).
+pub fn erfc(x: Real) -> Real {
+    let z = (x as f64).abs();
+    let t = 1. / (1. + 0.5 * z);
+    let poly = -z * z - 1.26551223
+        + t * (1.00002368
+            + t * (0.37409196
+                + t * (0.09678418
+                    + t * (-0.18628806
+                        + t * (0.27886807
+                            + t * (-1.13520398
+                                + t * (1.48851587 + t * (-0.82215223 + t * 0.17087277))))))));
+    let result = t * poly.exp();
+    (if x >= 0. { result } else { 2. - result }) as Real
+}
+
+/// Adds the symmetric tensor `A [δ_ab - 2 (1 + k² / 4α²) k_a k_b / k²]` of one
+/// reciprocal vector to `w`.
+fn add_reciprocal_virial(w: &mut [[f64; 3]; 3], k: &[f64; 3], kk: f64, alpha: f64, weight: f64) {
+    let c = 2. * (1. + kk / (4. * alpha * alpha)) / kk;
+    for a in 0..3 {
+        for b in 0..3 {
+            let delta = if a == b { 1. } else { 0. };
+            w[a][b] += weight * (delta - c * k[a] * k[b]);
+        }
+    }
+}
+
+/// Direct sum over the reciprocal vectors of half of k-space: returns the energy and
+/// virial tensor without the prefactor and adds the forces to `forces`.
+fn reciprocal_sum(
+    pos: &[DVector<3>],
+    charges: &[f64],
+    box_size: &[Real; 3],
+    params: &EwaldParameters,
+    forces: &mut [[f64; 3]],
+) -> (f64, [[f64; 3]; 3]) {
+    let alpha = params.alpha as f64;
+    let k_cut = params.k_cut as f64;
+    let volume: f64 = box_size.iter().map(|l| *l as f64).product();
+    let unit = box_size.map(|l| 2. * PI / l as f64);
+    let [nx, ny, nz] = params.k_max.map(|n| n as i64);
+
+    // e^(i n 2π x_a / L_a) for n in 0..=k_max_a, per molecule and axis
+    let phases: Vec<[Vec<Complex64>; 3]> = pos
+        .iter()
+        .map(|p| {
+            std::array::from_fn(|a| {
+                let base = Complex64::from_polar(1., unit[a] * p.components()[a] as f64);
+                let mut powers = vec![Complex64::new(1., 0.); params.k_max[a] + 1];
+                for n in 1..powers.len() {
+                    powers[n] = powers[n - 1] * base;
+                }
+                powers
+            })
+        })
+        .collect();
+    let phase = |n: i64, powers: &[Complex64]| {
+        let value = powers[n.unsigned_abs() as usize];
+        if n < 0 {
+            value.conj()
+        } else {
+            value
+        }
+    };
+
+    let mut energy = 0.;
+    let mut w = [[0.; 3]; 3];
+    let mut terms = vec![Complex64::default(); pos.len()];
+    for mx in 0..=nx {
+        for my in -ny..=ny {
+            for mz in -nz..=nz {
+                if mx == 0 && (my < 0 || (my == 0 && mz <= 0)) {
+                    continue;
+                }
+                let k = [
+                    mx as f64 * unit[0],
+                    my as f64 * unit[1],
+                    mz as f64 * unit[2],
+                ];
+                let kk = k[0] * k[0] + k[1] * k[1] + k[2] * k[2];
+                if kk > k_cut * k_cut {
+                    continue;
+                }
+                let mut structure = Complex64::default();
+                for (j, p) in phases.iter().enumerate() {
+                    terms[j] = phase(mx, &p[0]) * phase(my, &p[1]) * phase(mz, &p[2]);
+                    structure += charges[j] * terms[j];
+                }
+                // Both k and -k
+                let a_k = 4. * PI / (volume * kk) * (-kk / (4. * alpha * alpha)).exp();
+                let weight = a_k * structure.norm_sqr();
+                energy += weight;
+                add_reciprocal_virial(&mut w, &k, kk, alpha, weight);
+                for (j, f) in forces.iter_mut().enumerate() {
+                    let push = 2. * a_k * charges[j] * (structure.conj() * terms[j]).im;
+                    for a in 0..3 {
+                        f[a] += push * k[a];
+                    }
+                }
+            }
+        }
+    }
+    (energy, w)
+}
+
+/// Cardinal B-spline weights `M_p(x + t)` and their derivatives for `t` in `0..order`.
+fn bspline(x: f64, order: usize) -> (Vec<f64>, Vec<f64>) {
+    let mut m = vec![0.; order];
+    m[0] = x;
+    m[1] = 1. - x;
+    let mut dm = vec![0.; order];
+    for n in 3..=order {
+        if n == order {
+            dm[0] = m[0];
+            for t in 1..order {
+                dm[t] = m[t] - m[t - 1];
+            }
+        }
+        let n_f = n as f64;
+        for t in (0..n).rev() {
+            let below = if t > 0 { m[t - 1] } else { 0. };
+            m[t] = ((x + t as f64) * m[t] + (n_f - x - t as f64) * below) / (n_f - 1.);
+        }
+    }
+    (m, dm)
+}
+
+/// `|b(m)|²` of the smooth PME interpolation along one axis of `n` points.
+fn bspline_moduli(n: usize, order: usize) -> Vec<f64> {
+    let (m, _) = bspline(0., order);
+    (0..n)
+        .map(|i| {
+            let mut sum = Complex64::default();
+            for t in 0..order - 1 {
+                sum += m[t + 1] * Complex64::from_polar(1., 2. * PI * (i * t) as f64 / n as f64);
+            }
+            let norm = sum.norm_sqr();
+            if norm > 1e-10 {
+                1. / norm
+            } else {
+                0.
+            }
+        })
+        .collect()
+}
+
+/// In-place unnormalized FFT of a row-major grid.
+fn fft_3d(
+    grid: &mut [Complex64],
+    dims: [usize; 3],
+    direction: FftDirection,
+    planner: &mut FftPlanner<f64>,
+) {
+    let [n0, n1, n2] = dims;
+    planner.plan_fft(n2, direction).process(grid);
+    let mut line = vec![Complex64::default(); n0.max(n1)];
+    let fft = planner.plan_fft(n1, direction);
+    for i0 in 0..n0 {
+        for i2 in 0..n2 {
+            for i1 in 0..n1 {
+                line[i1] = grid[(i0 * n1 + i1) * n2 + i2];
+            }
+            fft.process(&mut line[..n1]);
+            for i1 in 0..n1 {
+                grid[(i0 * n1 + i1) * n2 + i2] = line[i1];
+            }
+        }
+    }
+    let fft = planner.plan_fft(n0, direction);
+    for i1 in 0..n1 {
+        for i2 in 0..n2 {
+            for i0 in 0..n0 {
+                line[i0] = grid[(i0 * n1 + i1) * n2 + i2];
+            }
+            fft.process(&mut line[..n0]);
+            for i0 in 0..n0 {
+                grid[(i0 * n1 + i1) * n2 + i2] = line[i0];
+            }
+        }
+    }
+}
+
+/// Mesh points, B-spline weights and their derivatives along one axis.
+type Stencil = (Vec<usize>, Vec<f64>, Vec<f64>);
+
+/// Smooth PME (Essmann et al. 1995): the reciprocal part from charges spread on a
+/// mesh, with the same results and conventions as `reciprocal_sum`.
+fn mesh_sum(
+    pos: &[DVector<3>],
+    charges: &[f64],
+    box_size: &[Real; 3],
+    params: &EwaldParameters,
+    order: usize,
+    planner: &mut FftPlanner<f64>,
+    forces: &mut [[f64; 3]],
+) -> (f64, [[f64; 3]; 3]) {
+    let alpha = params.alpha as f64;
+    let dims = params.mesh;
+    let [n0, n1, n2] = dims;
+    let lengths = box_size.map(|l| l as f64);
+    let volume: f64 = lengths.iter().product();
+
+    // Mesh points and weights along each axis, per molecule
+    let stencils: Vec<[Stencil; 3]> = pos
+        .iter()
+        .map(|p| {
+            std::array::from_fn(|a| {
+                let u = dims[a] as f64 * (p.components()[a] as f64 / lengths[a] + 0.5);
+                let floor = u.floor();
+                let (w, dw) = bspline(u - floor, order);
+                let index = (0..order)
+                    .map(|t| (floor as i64 - t as i64).rem_euclid(dims[a] as i64) as usize)
+                    .collect();
+                (index, w, dw)
+            })
+        })
+        .collect();
+    let flat = |i0: usize, i1: usize, i2: usize| (i0 * n1 + i1) * n2 + i2;
+
+    let mut grid = vec![Complex64::default(); n0 * n1 * n2];
+    for (q, [(x, wx, _), (y, wy, _), (z, wz, _)]) in charges.iter().zip(stencils.iter()) {
+        for (i0, w0) in x.iter().zip(wx) {
+            for (i1, w1) in y.iter().zip(wy) {
+                for (i2, w2) in z.iter().zip(wz) {
+                    grid[flat(*i0, *i1, *i2)] += q * w0 * w1 * w2;
+                }
+            }
+        }
+    }
+    fft_3d(&mut grid, dims, FftDirection::Forward, planner);
+
+    let moduli = dims.map(|n| bspline_moduli(n, order));
+    let frequency = |i: usize, n: usize| {
+        if i <= n / 2 {
+            i as f64
+        } else {
+            i as f64 - n as f64
+        }
+    };
+    let mut energy = 0.;
+    let mut w = [[0.; 3]; 3];
+    for i0 in 0..n0 {
+        for i1 in 0..n1 {
+            for i2 in 0..n2 {
+                let index = flat(i0, i1, i2);
+                let m = [
+                    frequency(i0, n0) / lengths[0],
+                    frequency(i1, n1) / lengths[1],
+                    frequency(i2, n2) / lengths[2],
+                ];
+                let mm = m[0] * m[0] + m[1] * m[1] + m[2] * m[2];
+                if mm == 0. {
+                    grid[index] = Complex64::default();
+                    continue;
+                }
+                let g = (-PI * PI * mm / (alpha * alpha)).exp() / (PI * volume * mm)
+                    * moduli[0][i0]
+                    * moduli[1][i1]
+                    * moduli[2][i2];
+                let weight = 0.5 * g * grid[index].norm_sqr();
+                energy += weight;
+                let k = m.map(|m| 2. * PI * m);
+                add_reciprocal_virial(&mut w, &k, 4. * PI * PI * mm, alpha, weight);
+                grid[index] *= g;
+            }
+        }
+    }
+    fft_3d(&mut grid, dims, FftDirection::Inverse, planner);
+
+    for ((q, [(x, wx, dx), (y, wy, dy), (z, wz, dz)]), f) in
+        charges.iter().zip(stencils.iter()).zip(forces.iter_mut())
+    {
+        let mut gradient = [0.; 3];
+        for ((i0, w0), d0) in x.iter().zip(wx).zip(dx) {
+            for ((i1, w1), d1) in y.iter().zip(wy).zip(dy) {
+                for ((i2, w2), d2) in z.iter().zip(wz).zip(dz) {
+                    let phi = grid[flat(*i0, *i1, *i2)].re;
+                    gradient[0] += d0 * w1 * w2 * phi;
+                    gradient[1] += w0 * d1 * w2 * phi;
+                    gradient[2] += w0 * w1 * d2 * phi;
+                }
+            }
+        }
+        for a in 0..3 {
+            f[a] -= q * gradient[a] * dims[a] as f64 / lengths[a];
+        }
+    }
+    (energy, w)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{boundaries::Region, test_support};
+
+    /// Rock salt with unit nearest-neighbor distance, `n³` ions.
+    fn rock_salt(n: usize) -> (Region<3>, Vec<DVector<3>>, Vec<Real>) {
+        let offset = 0.5 * n as Real;
+        let mut pos = Vec::new();
+        let mut charges = Vec::new();
+        for i in 0..n * n * n {
+            let site = [i % n, i / n % n, i / (n * n)];
+            pos.push(DVector::from(site.map(|c| c as Real - offset)));
+            charges.push(if site.iter().sum::<usize>() % 2 == 0 {
+                1.
+            } else {
+                -1.
+            });
+        }
+        (Region::new([n as Real; 3]), pos, charges)
+    }
+
+    /// Cesium chloride, `n³` unit cells of side 1 with the anion at the cell center.
+    fn cesium_chloride(n: usize) -> (Region<3>, Vec<DVector<3>>, Vec<Real>) {
+        let offset = 0.5 * n as Real;
+        let mut pos = Vec::new();
+        let mut charges = Vec::new();
+        for i in 0..n * n * n {
+            let site = [i % n, i / n % n, i / (n * n)].map(|c| c as Real - offset);
+            pos.push(DVector::from(site));
+            charges.push(1.);
+            pos.push(DVector::from(site.map(|c| c + 0.5)));
+            charges.push(-1.);
+        }
+        (Region::new([n as Real; 3]), pos, charges)
+    }
+
+    fn evaluate(
+        mut ewald: Ewald,
+        region: &Region<3>,
+        pos: &[DVector<3>],
+        charges: &[Real],
+    ) -> (Ewald, Vec<DVector<3>>) {
+        PotentialEnergy::<3>::set_charges(&mut ewald, charges);
+        let mut acc = vec![DVector::default(); pos.len()];
+        ewald.compute_forces(pos, &mut acc, region);
+        (ewald, acc)
+    }
+
+    /// Energy per ion pair `-M / d` at nearest-neighbor distance `d`, no net forces,
+    /// and the Coulomb virial equal to the energy.
+    fn assert_madelung(
+        ewald: Ewald,
+        lattice: (Region<3>, Vec<DVector<3>>, Vec<Real>),
+        d: Real,
+        madelung: Real,
+    ) {
+        let (region, pos, charges) = lattice;
+        let (ewald, acc) = evaluate(ewald, &region, &pos, &charges);
+        let u = ewald.u_sum();
+        let per_pair = u / (0.5 * pos.len() as Real);
+        assert!((per_pair * d + madelung).abs() < 1e-4, "{per_pair}");
+        assert!(acc.iter().all(|a| a.length() < 1e-3));
+        assert!((ewald.virial_sum() - u).abs() < 1e-3 * u.abs());
+        let w = ewald.virial_tensor().unwrap();
+        assert!((w[0][0] - w[2][2]).abs() < 1e-3 * u.abs() && w[0][1].abs() < 1e-3 * u.abs());
+    }
+
+    #[test]
+    fn rock_salt_madelung() {
+        assert_madelung(Ewald::new(1e-5), rock_salt(4), 1., 1.747565);
+        assert_madelung(Ewald::pme(1e-5), rock_salt(4), 1., 1.747565);
+        assert_madelung(Ewald::pme(1e-5).r_cut(1.5), rock_salt(6), 1., 1.747565);
+    }
+
+    #[test]
+    fn cesium_chloride_madelung() {
+        let d = 0.5 * (3 as Real).sqrt();
+        assert_madelung(Ewald::new(1e-5), cesium_chloride(3), d, 1.762675);
+        assert_madelung(Ewald::pme(1e-5), cesium_chloride(3), d, 1.762675);
+    }
+
+    #[test]
+    fn mesh_matches_direct_sum() {
+        let (region, mut pos, charges) = rock_salt(6);
+        test_support::jitter(&mut pos, 0.3, 5);
+        let (direct, acc_direct) = evaluate(Ewald::new(1e-5), &region, &pos, &charges);
+        let (mesh, acc_mesh) = evaluate(Ewald::pme(1e-5), &region, &pos, &charges);
+        for (a, b) in acc_direct.iter().zip(acc_mesh.iter()) {
+            assert!((a - b).length() < 1e-2 * (1. + a.length()));
+        }
+        let u = direct.u_sum();
+        assert!((mesh.u_sum() - u).abs() < 1e-4 * u.abs());
+        assert!((direct.virial_sum() - u).abs() < 1e-3 * u.abs());
+        assert!((mesh.virial_sum() - u).abs() < 1e-3 * u.abs());
+    }
+
+    #[test]
+    fn forces_match_energy() {
+        let (region, mut pos, charges) = rock_salt(4);
+        test_support::jitter(&mut pos, 0.3, 6);
+        for ewald in [Ewald::new(1e-6), Ewald::pme(1e-6)] {
+            let (ewald, acc) = evaluate(ewald, &region, &pos, &charges);
+            let mut scratch = acc.clone();
+            let h = 1e-3;
+            for j in [0, 5, 17] {
+                let mut moved = pos.clone();
+                moved[j] += &DVector::from([h, 0., 0.]);
+                ewald.compute_forces(&moved, &mut scratch, &region);
+                let u_plus = ewald.u_sum();
+                moved[j] -= &DVector::from([2. * h, 0., 0.]);
+                ewald.compute_forces(&moved, &mut scratch, &region);
+                let force = -(u_plus - ewald.u_sum()) / (2. * h);
+                let f = acc[j].components()[0];
+                assert!((f - force).abs() < 2e-2 * (1. + f.abs()), "{f} vs {force}");
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+#![allow(unused, dead_code)]
+
+use crate::{cells::Pairs, potential::VirialTensor};
+use atomic_float::AtomicF32;
+use d_vector::{DVector, Real};
+use std::{
+    ops::Range,
+    sync::{atomic::Ordering, Mutex},
+};
+
+/// Contribution of a single interacting pair: the separation `dr = r1 - r2`,
+/// the force acting on the first molecule (the second one gets the opposite),
+/// pair energy and `r · f`.
+#[derive(Debug, Clone, Default)]
+pub struct PairTerm<const D: usize> {
+    pub dr: DVector<D>,
+    pub force: DVector<D>,
+    pub u: Real,
+    pub virial: Real,
+}
+
+#[derive(Debug, Clone)]
+pub struct PairSums<const D: usize> {
+    pub u: Real,
+    pub virial: Real,
+    pub virial_tensor: VirialTensor<D>,
+}
+
+impl<const D: usize> Default for PairSums<D> {
+    fn default() -> Self {
+        Self {
+            u: 0.,
+            virial: 0.,
+            virial_tensor: [[0.; D]; D],
+        }
+    }
+}
+
+impl<const D: usize> PairSums<D> {
+    pub fn add_pair(&mut self, pair: &PairTerm<D>) {
+        self.u += pair.u;
+        self.virial += pair.virial;
+        for (row, a) in self.virial_tensor.iter_mut().zip(pair.dr.components()) {
+            for (w, b) in row.iter_mut().zip(pair.force.components()) {
+                *w += a * b;
+            }
+        }
+    }
+
+    pub fn add(&mut self, other: &Self) {
+        self.u += other.u;
+        self.virial += other.virial;
+        for (row, other_row) in self
+            .virial_tensor
+            .iter_mut()
+            .zip(other.virial_tensor.iter())
+        {
+            for (w, o) in row.iter_mut().zip(other_row.iter()) {
+                *w += o;
+            }
+        }
+    }
+}
+
+/// Per-molecule shares of the pair terms: half of every pair energy and
+/// of every `dr_a f_b` goes to each of the two molecules.
+#[derive(Debug, Clone, Default)]
+pub struct ParticleSums<const D: usize> {
+    pub energy: Vec<Real>,
+    pub virial: Vec<VirialTensor<D>>,
+}
+
+impl<const D: usize> ParticleSums<D> {
+    pub fn new(n_mol: usize) -> Self {
+        Self {
+            energy: vec![0.; n_mol],
+            virial: vec![[[0.; D]; D]; n_mol],
+        }
+    }
+
+    pub fn add_pair(&mut self, j1: usize, j2: usize, pair: &PairTerm<D>) {
+        self.energy[j1] += 0.5 * pair.u;
+        self.energy[j2] += 0.5 * pair.u;
+        for (a, dr) in pair.dr.components().iter().enumerate() {
+            for (b, f) in pair.force.components().iter().enumerate() {
+                let w = 0.5 * dr * f;
+                self.virial[j1][a][b] += w;
+                self.virial[j2][a][b] += w;
+            }
+        }
+    }
+
+    pub fn add(&mut self, other: &Self) {
+        for (e, o) in self.energy.iter_mut().zip(other.energy.iter()) {
+            *e += o;
+        }
+        for (w, o) in self.virial.iter_mut().zip(other.virial.iter()) {
+            for (row, other_row) in w.iter_mut().zip(o.iter()) {
+                for (w, o) in row.iter_mut().zip(other_row.iter()) {
+                    *w += o;
+                }
+            }
+        }
+    }
+}
+
+/// Last step's sums kept by a potential between `compute_forces` calls.
+/// The virial tensor is stored flattened, so potentials need not be generic over `D`.
+#[derive(Debug, Default)]
+pub struct StoredSums {
+    u_sum: AtomicF32,
+    v_sum: AtomicF32,
+    w_sum: Mutex<Vec<Real>>,
+    particle_energies: Mutex<Vec<Real>>,
+    particle_virials: Mutex<Vec<Real>>,
+}
+
+impl StoredSums {
+    pub fn store<const D: usize>(&self, sums: &PairSums<D>) {
+        self.u_sum.store(sums.u, Ordering::SeqCst);
+        self.v_sum.store(sums.virial, Ordering::SeqCst);
+        let mut w_sum = self.w_sum.lock().unwrap();
+        w_sum.clear();
+        for row in sums.virial_tensor.iter() {
+            w_sum.extend_from_slice(row);
+        }
+    }
+
+    pub fn store_particles<const D: usize>(&self, particles: &ParticleSums<D>) {
+        let mut energies = self.particle_energies.lock().unwrap();
+        energies.clear();
+        energies.extend_from_slice(&particles.energy);
+        let mut virials = self.particle_virials.lock().unwrap();
+        virials.clear();
+        for row in particles.virial.iter().flat_map(|w| w.iter()) {
+            virials.extend_from_slice(row);
+        }
+    }
+
+    pub fn u_sum(&self) -> Real {
+        self.u_sum.load(Ordering::SeqCst)
+    }
+
+    pub fn virial_sum(&self) -> Real {
+        self.v_sum.load(Ordering::SeqCst)
+    }
+
+    pub fn virial_tensor<const D: usize>(&self) -> Option<VirialTensor<D>> {
+        let w_sum = self.w_sum.lock().unwrap();
+        if w_sum.len() != D * D {
+            return None;
+        }
+        let mut result = [[0.; D]; D];
+        for (row, chunk) in result.iter_mut().zip(w_sum.chunks_exact(D)) {
+            row.copy_from_slice(chunk);
+        }
+        Some(result)
+    }
+
+    pub fn particle_energies(&self) -> Option<Vec<Real>> {
+        let energies = self.particle_energies.lock().unwrap();
+        (!energies.is_empty()).then(|| energies.clone())
+    }
+
+    pub fn particle_virials<const D: usize>(&self) -> Option<Vec<VirialTensor<D>>> {
+        let virials = self.particle_virials.lock().unwrap();
+        if virials.is_empty() || !virials.len().is_multiple_of(D * D) {
+            return None;
+        }
+        let result = virials
+            .chunks_exact(D * D)
+            .map(|chunk| {
+                let mut w = [[0.; D]; D];
+                for (row, values) in w.iter_mut().zip(chunk.chunks_exact(D)) {
+                    row.copy_from_slice(values);
+                }
+                w
+            })
+            .collect();
+        Some(result)
+    }
+}
+
+/// Adds pair forces to `acc` and returns the sums of energy and virial;
+/// the per-molecule shares go to `particles` when given.
+/// With the `parallel` feature the units of `pairs` are split into one chunk
+/// per rayon thread, each chunk accumulating into its own force array;
+/// the chunks are reduced in order, so the result depends on the thread count only.
+pub fn accumulate<const D: usize>(
+    pairs: &Pairs<'_, D>,
+    acc: &mut [DVector<D>],
+    particles: Option<&mut ParticleSums<D>>,
+    term: impl Fn(usize, usize) -> Option<PairTerm<D>> + Sync,
+) -> PairSums<D> {
+    #[cfg(feature = "parallel")]
+    {
+        accumulate_parallel(pairs, acc, particles, &term)
+    }
+    #[cfg(not(feature = "parallel"))]
+    {
+        accumulate_range(pairs, 0..pairs.units(), acc, particles, &term)
+    }
+}
+
+fn accumulate_range<const D: usize>(
+    pairs: &Pairs<'_, D>,
+    units: Range<usize>,
+    acc: &mut [DVector<D>],
+    mut particles: Option<&mut ParticleSums<D>>,
+    term: &impl Fn(usize, usize) -> Option<PairTerm<D>>,
+) -> PairSums<D> {
+    let mut sums = PairSums::default();
+    pairs.for_each_pair_in(units, |j1, j2| {
+        if let Some(pair) = term(j1, j2) {
+            acc[j1] += &pair.force;
+            acc[j2] -= &pair.force;
+            sums.add_pair(&pair);
+            if let Some(particles) = particles.as_deref_mut() {
+                particles.add_pair(j1, j2, &pair);
+            }
+        }
+    });
+    sums
+}
+
+#[cfg(feature = "parallel")]
+fn accumulate_parallel<const D: usize>(
+    pairs: &Pairs<'_, D>,
+    acc: &mut [DVector<D>],
+    particles: Option<&mut ParticleSums<D>>,
+    term: &(impl Fn(usize, usize) -> Option<PairTerm<D>> + Sync),
+) -> PairSums<D> {
+    use rayon::prelude::*;
+
+    let n_mol = acc.len();
+    let units = pairs.units();
+    let n_chunks = rayon::current_num_threads().max(1);
+    let chunk = units.div_ceil(n_chunks);
+    let with_particles = particles.is_some();
+    let partial: Vec<_> = (0..n_chunks)
+        .into_par_iter()
+        .map(|k| {
+            let range = (k * chunk).min(units)..((k + 1) * chunk).min(units);
+            let mut local = vec![DVector::default(); n_mol];
+            let mut local_particles = with_particles.then(|| ParticleSums::new(n_mol));
+            let sums = accumulate_range(pairs, range, &mut local, local_particles.as_mut(), term);
+            (local, local_particles, sums)
+        })
+        .collect();
+
+    let mut result = PairSums::default();
+    let mut particles = particles;
+    for (local, local_particles, sums) in partial {
+        for (a, l) in acc.iter_mut().zip(local.iter()) {
+            *a += l;
+        }
+        if let (Some(p), Some(l)) = (particles.as_deref_mut(), local_particles.as_ref()) {
+            p.add(l);
+        }
+        result.add(&sums);
+    }
+    result
+}
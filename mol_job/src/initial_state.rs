@@ -2,6 +2,7 @@
 
 use crate::boundaries::Region;
 use d_vector::{DVector, Real};
+use rand::seq::SliceRandom;
 use std::ops::AddAssign;
 
 pub fn cubic_lattice<const D: usize>(n_mol: usize, density: Real) -> (Region<D>, Vec<DVector<D>>) {
@@ -18,6 +19,22 @@ pub fn cubic_lattice<const D: usize>(n_mol: usize, density: Real) -> (Region<D>,
     (region, pos)
 }
 
+/// Randomly ordered particle types in the given proportions; the counts are
+/// rounded so that they add up to `n_mol`.
+pub fn composition(n_mol: usize, fractions: &[Real]) -> Vec<usize> {
+    let total: Real = fractions.iter().sum();
+    let mut types = Vec::with_capacity(n_mol);
+    let mut cumulative = 0.;
+    for (t, fraction) in fractions.iter().enumerate() {
+        cumulative += fraction / total;
+        let count = (cumulative * n_mol as Real).round() as usize;
+        types.resize(count.clamp(types.len(), n_mol), t);
+    }
+    types.resize(n_mol, fractions.len().saturating_sub(1));
+    types.shuffle(&mut rand::thread_rng());
+    types
+}
+
 fn number_of_atoms(cells: &[usize]) -> usize {
     let mut result = 1;
     for cell in cells {
@@ -0,0 +1,179 @@
+#![allow(unused, dead_code)]
+
+use crate::{boundaries::BoundaryConditions, potential::PotentialEnergy, verlet};
+use d_vector::{DVector, Real};
+use std::{fmt::Debug, ops::AddAssign};
+
+pub trait Integrator<const D: usize>: Debug + Send + Sync {
+    #[allow(clippy::too_many_arguments)]
+    fn single_step(
+        &self,
+        delta_t: Real,
+        pos: &mut [DVector<D>],
+        vel: &mut [DVector<D>],
+        acc: &mut [DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        potential_energy: &dyn PotentialEnergy<D>,
+    );
+}
+
+#[derive(Debug, Default)]
+pub struct Leapfrog;
+
+impl<const D: usize> Integrator<D> for Leapfrog {
+    fn single_step(
+        &self,
+        delta_t: Real,
+        pos: &mut [DVector<D>],
+        vel: &mut [DVector<D>],
+        acc: &mut [DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        potential_energy: &dyn PotentialEnergy<D>,
+    ) {
+        verlet::single_step(delta_t, pos, vel, acc, masses, boundaries, potential_energy);
+    }
+}
+
+/// Velocity Verlet in position form: a full drift with the current forces, then
+/// the two half kicks, so `vel` is in step with `pos` at the end of the step.
+#[derive(Debug, Default)]
+pub struct VelocityVerlet;
+
+impl<const D: usize> Integrator<D> for VelocityVerlet {
+    fn single_step(
+        &self,
+        delta_t: Real,
+        pos: &mut [DVector<D>],
+        vel: &mut [DVector<D>],
+        acc: &mut [DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        potential_energy: &dyn PotentialEnergy<D>,
+    ) {
+        assert_eq!(pos.len(), vel.len());
+        assert_eq!(vel.len(), acc.len());
+        let half_delta_t = delta_t / 2.;
+        for ((position, velocity), acceleration) in
+            pos.iter_mut().zip(vel.iter_mut()).zip(acc.iter())
+        {
+            position.add_assign(delta_t * &*velocity);
+            position.add_assign((half_delta_t * delta_t) * acceleration);
+            velocity.add_assign(half_delta_t * acceleration);
+        }
+        verlet::apply_boundary_conditions(boundaries, pos);
+        verlet::compute_accelerations(potential_energy, pos, acc, masses, boundaries);
+        for (velocity, acceleration) in vel.iter_mut().zip(acc.iter()) {
+            velocity.add_assign(half_delta_t * acceleration);
+        }
+    }
+}
+
+/// Fourth-order Forest–Ruth (Yoshida) scheme: three leapfrog substeps of
+/// `θ Δt`, `(1 - 2θ) Δt` and `θ Δt` with `θ = 1 / (2 - 2^(1/3))`.
+#[derive(Debug, Default)]
+pub struct ForestRuth;
+
+impl ForestRuth {
+    pub fn weights() -> [Real; 3] {
+        let theta = 1. / (2. - (2 as Real).cbrt());
+        [theta, 1. - 2. * theta, theta]
+    }
+}
+
+impl<const D: usize> Integrator<D> for ForestRuth {
+    fn single_step(
+        &self,
+        delta_t: Real,
+        pos: &mut [DVector<D>],
+        vel: &mut [DVector<D>],
+        acc: &mut [DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        potential_energy: &dyn PotentialEnergy<D>,
+    ) {
+        for w in Self::weights() {
+            verlet::single_step(
+                w * delta_t,
+                pos,
+                vel,
+                acc,
+                masses,
+                boundaries,
+                potential_energy,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{boundaries::Region, test_support::Spring};
+
+    fn max_energy_error(integrator: &dyn Integrator<1>, delta_t: Real) -> Real {
+        let region = Region::new([100.]);
+        let spring = Spring::default();
+        let mut pos = vec![DVector::from([1.])];
+        let mut vel = vec![DVector::from([0.])];
+        let mut acc = vec![DVector::from([-1.])];
+        let mut result: Real = 0.;
+        for _ in 0..(std::f32::consts::TAU / delta_t) as usize {
+            integrator.single_step(delta_t, &mut pos, &mut vel, &mut acc, &[], &region, &spring);
+            let e = spring.u_sum() + 0.5 * vel[0].square_length();
+            result = result.max((e - 0.5).abs());
+        }
+        result
+    }
+
+    #[test]
+    fn heavy_spring_period() {
+        let region = Region::new([100.]);
+        let spring = Spring::default();
+        let mut pos = vec![DVector::from([1.])];
+        let mut vel = vec![DVector::from([0.])];
+        let mut acc = vec![DVector::from([-0.25])];
+        let delta_t = 0.01;
+        // ω = 1 / √4, so half a period takes 2π
+        for _ in 0..(std::f32::consts::TAU / delta_t).round() as usize {
+            VelocityVerlet.single_step(
+                delta_t,
+                &mut pos,
+                &mut vel,
+                &mut acc,
+                &[4.],
+                &region,
+                &spring,
+            );
+        }
+        assert!((pos[0].components()[0] + 1.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn energy_error_order() {
+        let leapfrog = max_energy_error(&Leapfrog, 0.1);
+        let verlet = max_energy_error(&VelocityVerlet, 0.1);
+        let forest_ruth = max_energy_error(&ForestRuth, 0.1);
+        assert!((leapfrog - verlet).abs() < 1e-5);
+        assert!(leapfrog > 1e-3 && leapfrog < 2e-3);
+        assert!(forest_ruth < 0.05 * leapfrog);
+    }
+
+    #[test]
+    fn synchronised_velocities() {
+        let region = Region::new([100.]);
+        let spring = Spring::default();
+        let mut pos = vec![DVector::from([1.])];
+        let mut vel = vec![DVector::from([0.])];
+        let mut acc = vec![DVector::from([-1.])];
+        let delta_t: Real = 0.1;
+        VelocityVerlet.single_step(delta_t, &mut pos, &mut vel, &mut acc, &[], &region, &spring);
+        let x = pos[0].components()[0];
+        assert!((x - (1. - 0.5 * delta_t * delta_t)).abs() < 1e-6);
+        // v(Δt) = (a(0) + a(Δt)) Δt / 2, against the exact -sin(Δt)
+        let v = vel[0].components()[0];
+        assert!((v + 0.5 * delta_t * (1. + x)).abs() < 1e-6);
+        assert!((v + delta_t.sin()).abs() < 1e-3);
+    }
+}
@@ -1,11 +1,15 @@
 #![allow(unused, dead_code)]
 
 use crate::{
-    boundaries::{BoundaryConditions, Region},
+    barostat::{self, Barostat, NoBarostat},
+    boundaries::{BoundaryConditions, Images, Region},
+    composite::CompositePotential,
+    integrator::{Integrator, Leapfrog},
     lennard_jones::LennardJones,
-    potential::PotentialEnergy,
+    potential::{PotentialEnergy, VirialTensor},
     prop::{Props, TrivialProps},
-    state::{MolecularState, State},
+    state::{self, MolecularState, State},
+    thermostat::{self, NoThermostat, Thermostat},
     verlet,
 };
 use d_vector::{DVector, Real};
@@ -19,7 +23,11 @@ pub struct Job<const D: usize> {
     state: Box<dyn MolecularState<D>>,
     boundaries: Box<dyn BoundaryConditions<D>>,
     potential: Box<dyn PotentialEnergy<D>>,
+    integrator: Box<dyn Integrator<D>>,
+    thermostat: Box<dyn Thermostat<D>>,
+    barostat: Box<dyn Barostat<D>>,
     props: Box<dyn Props<D>>,
+    images: Images<D>,
     step_count: usize,
     delta_t: Real,
     more_cycles: bool,
@@ -31,7 +39,11 @@ impl<const D: usize> Default for Job<D> {
             state: Box::new(State::default()),
             boundaries: Box::new(Region::new([50.; D])),
             potential: Box::new(LennardJones::default()),
-            props: Box::new(TrivialProps::default()),
+            integrator: Box::new(Leapfrog),
+            thermostat: Box::new(NoThermostat),
+            barostat: Box::new(NoBarostat),
+            props: Box::new(TrivialProps),
+            images: Images::default(),
             step_count: 0,
             delta_t: 0.005,
             more_cycles: true,
@@ -43,16 +55,48 @@ impl<const D: usize> Job<D> {
     pub fn run(&mut self, steps: usize) -> usize {
         self.more_cycles = true;
         let step_limit = self.step_count() + steps;
+        self.potential
+            .set_types(state::or_empty(&self.state.get_types()));
+        self.potential
+            .set_charges(state::or_empty(&self.state.get_charges()));
         while self.more_cycles {
             self.advance_step_count();
-            verlet::single_step(
+            self.thermostat.before_step(
+                self.delta_t,
+                &mut self.state.get_vel(),
+                state::or_empty(&self.state.get_masses()),
+            );
+            self.barostat.before_step(
+                self.delta_t,
+                &mut self.state.get_pos(),
+                &mut self.state.get_vel(),
+                state::or_empty(&self.state.get_masses()),
+                self.boundaries.as_mut(),
+                self.potential.as_ref(),
+            );
+            self.integrator.single_step(
                 self.delta_t(),
                 &mut self.state.get_pos(),
                 &mut self.state.get_vel(),
                 &mut self.state.get_acc(),
+                state::or_empty(&self.state.get_masses()),
                 self.boundaries.as_ref(),
                 self.potential.as_ref(),
             );
+            self.barostat.after_step(
+                self.delta_t,
+                &mut self.state.get_vel(),
+                state::or_empty(&self.state.get_masses()),
+                self.boundaries.as_ref(),
+                self.potential.as_ref(),
+            );
+            self.thermostat.after_step(
+                self.delta_t,
+                &mut self.state.get_vel(),
+                state::or_empty(&self.state.get_masses()),
+            );
+            self.images
+                .track(&self.state.get_pos(), self.boundaries.as_ref());
             self.update_props();
             self.state.sync(self.time_now());
 
@@ -72,16 +116,15 @@ impl<const D: usize> Job<D> {
     }
 
     fn update_props(&self) {
-        /*         println!(
-            "{}. u = {}, v = {} <--- update_props()",
-            self.step_count(),
-            self.potential.u_sum(),
-            self.potential.virial_sum()
-        );*/
         self.props.eval_props(
             self.potential.as_ref(),
             &self.state.get_pos(),
             &self.state.get_vel(),
+            state::or_empty(&self.state.get_masses()),
+            self.boundaries.as_ref(),
+            self.images.unwrapped(),
+            self.delta_t,
+            self.time_now(),
         );
         self.props.accum_props();
         if self.props.need_avg(self.step_count()) {
@@ -91,6 +134,10 @@ impl<const D: usize> Job<D> {
         }
     }
 
+    pub fn props(&self) -> &dyn Props<D> {
+        self.props.as_ref()
+    }
+
     pub fn time_now(&self) -> Real {
         self.delta_t() * self.step_count() as Real
     }
@@ -99,6 +146,86 @@ impl<const D: usize> Job<D> {
         self.step_count
     }
 
+    pub fn temperature(&self) -> Real {
+        thermostat::temperature(
+            &self.state.get_vel(),
+            state::or_empty(&self.state.get_masses()),
+        )
+    }
+
+    pub fn kinetic_energy(&self) -> Real {
+        thermostat::kinetic_energy(
+            &self.state.get_vel(),
+            state::or_empty(&self.state.get_masses()),
+        )
+    }
+
+    pub fn potential_energy(&self) -> Real {
+        self.potential.u_sum()
+    }
+
+    /// Energy and virial of every term added with `JobSetup::add_potential`.
+    pub fn term_sums(&self) -> Option<Vec<(Real, Real)>> {
+        self.potential.term_sums()
+    }
+
+    pub fn extended_energy(&self) -> Real {
+        self.kinetic_energy()
+            + self.potential_energy()
+            + self.thermostat.conserved_energy()
+            + self.barostat.conserved_energy()
+    }
+
+    pub fn volume(&self) -> Option<Real> {
+        barostat::volume(self.boundaries.as_ref())
+    }
+
+    pub fn pressure(&self) -> Option<Real> {
+        self.volume().map(|volume| {
+            barostat::pressure(
+                &self.state.get_vel(),
+                state::or_empty(&self.state.get_masses()),
+                self.potential.virial_sum(),
+                volume,
+            )
+        })
+    }
+
+    pub fn pressure_tensor(&self) -> Option<VirialTensor<D>> {
+        self.volume().map(|volume| {
+            barostat::pressure_tensor(
+                &self.state.get_vel(),
+                state::or_empty(&self.state.get_masses()),
+                self.potential.as_ref(),
+                volume,
+            )
+        })
+    }
+
+    /// Rebuilds of the potential's neighbor list; for a `CompositePotential`, of the
+    /// first term that keeps one.
+    pub fn neighbor_rebuilds(&self) -> Option<usize> {
+        self.potential.neighbors().map(|list| list.rebuilds())
+    }
+
+    /// Steps run so far over neighbor list rebuilds; integrators with several force
+    /// evaluations per step update the list more often than once a step.
+    pub fn steps_per_rebuild(&self) -> Option<Real> {
+        self.potential
+            .neighbors()
+            .filter(|list| list.rebuilds() > 0)
+            .map(|list| self.step_count() as Real / list.rebuilds() as Real)
+    }
+
+    pub fn images(&self) -> &[[i32; D]] {
+        self.images.counts()
+    }
+
+    /// Positions with the periodic image crossings since `init_pos` undone.
+    pub fn unwrapped_pos(&self) -> &[DVector<D>] {
+        self.images.unwrapped()
+    }
+
     pub fn vel_sum(&self) -> DVector<D> {
         let mut result = DVector::default();
         for velocity in self.state.get_vel().iter() {
@@ -106,60 +233,203 @@ impl<const D: usize> Job<D> {
         }
         result
     }
+
+    /// Total momentum `Σ m v`.
+    pub fn momentum(&self) -> DVector<D> {
+        let masses = self.state.get_masses();
+        let mut result = DVector::default();
+        for (j, velocity) in self.state.get_vel().iter().enumerate() {
+            result += &(state::mass(state::or_empty(&masses), j) * velocity);
+        }
+        result
+    }
 }
 
-pub struct JobSetup<const D: usize>(Job<D>);
+pub struct JobSetup<const D: usize> {
+    job: Job<D>,
+    potential: Option<Box<dyn PotentialEnergy<D>>>,
+    terms: Option<CompositePotential<D>>,
+    type_masses: Option<Vec<Real>>,
+    type_charges: Option<Vec<Real>>,
+    temperature: Option<Real>,
+}
 
 impl<const D: usize> JobSetup<D> {
     pub fn build() -> Self {
-        let mut job = Job::default();
-        Self(job)
+        Self {
+            job: Job::default(),
+            potential: None,
+            terms: None,
+            type_masses: None,
+            type_charges: None,
+            temperature: None,
+        }
     }
 
     pub fn delta_t(mut self, dt: Real) -> Self {
-        self.0.delta_t = dt;
+        self.job.delta_t = dt;
         self
     }
 
     pub fn state(mut self, state: impl MolecularState<D> + 'static) -> Self {
-        self.0.state = Box::new(state);
+        self.job.state = Box::new(state);
         self
     }
 
     pub fn potential(mut self, potential: impl PotentialEnergy<D> + 'static) -> Self {
-        self.0.potential = Box::new(potential);
+        self.potential = Some(Box::new(potential));
+        self
+    }
+
+    /// Adds a term to a `CompositePotential`; a single `potential`, if also given,
+    /// becomes its first term once the job is built.
+    pub fn add_potential(mut self, potential: impl PotentialEnergy<D> + 'static) -> Self {
+        self.terms = Some(self.terms.unwrap_or_default().term(potential));
+        self
+    }
+
+    pub fn integrator(mut self, integrator: impl Integrator<D> + 'static) -> Self {
+        self.job.integrator = Box::new(integrator);
+        self
+    }
+
+    pub fn thermostat(mut self, thermostat: impl Thermostat<D> + 'static) -> Self {
+        self.job.thermostat = Box::new(thermostat);
+        self
+    }
+
+    pub fn barostat(mut self, barostat: impl Barostat<D> + 'static) -> Self {
+        self.job.barostat = Box::new(barostat);
         self
     }
 
     pub fn props(mut self, props: impl Props<D> + 'static) -> Self {
-        self.0.props = Box::new(props);
+        self.job.props = Box::new(props);
         self
     }
 
     pub fn boundaries(mut self, boundaries: impl BoundaryConditions<D> + 'static) -> Self {
-        self.0.boundaries = Box::new(boundaries);
+        self.job.boundaries = Box::new(boundaries);
         self
     }
 
     pub fn init_pos(mut self, pos: Vec<DVector<D>>) -> Self {
         let n_mol = pos.len();
-        *self.0.state.get_pos() = pos;
-        *self.0.state.get_vel() = vec![DVector::default(); n_mol];
-        *self.0.state.get_acc() = vec![DVector::default(); n_mol];
+        *self.job.state.get_pos() = pos;
+        *self.job.state.get_vel() = vec![DVector::default(); n_mol];
+        *self.job.state.get_acc() = vec![DVector::default(); n_mol];
+        self.job.images = Images::default();
+        self.job
+            .images
+            .track(&self.job.state.get_pos(), self.job.boundaries.as_ref());
+        self
+    }
+
+    /// Particle type of every molecule, e.g. from `initial_state::composition`.
+    pub fn types(mut self, types: Vec<usize>) -> Self {
+        *self
+            .job
+            .state
+            .get_types()
+            .expect("the state does not keep particle types") = types;
+        self
+    }
+
+    /// Mass of every molecule.
+    pub fn masses(mut self, masses: Vec<Real>) -> Self {
+        *self
+            .job
+            .state
+            .get_masses()
+            .expect("the state does not keep masses") = masses;
         self
     }
 
+    /// Mass of every particle type, given to the molecules when the job is built;
+    /// `job` panics if some molecule's type has no mass here.
+    pub fn type_masses(mut self, masses: &[Real]) -> Self {
+        self.type_masses = Some(masses.to_vec());
+        self
+    }
+
+    /// Charge of every molecule.
+    pub fn charges(mut self, charges: Vec<Real>) -> Self {
+        *self
+            .job
+            .state
+            .get_charges()
+            .expect("the state does not keep charges") = charges;
+        self
+    }
+
+    /// Charge of every particle type, given to the molecules when the job is built;
+    /// `job` panics if some molecule's type has no charge here.
+    pub fn type_charges(mut self, charges: &[Real]) -> Self {
+        self.type_charges = Some(charges.to_vec());
+        self
+    }
+
+    /// Random directions with speeds scaled by `1 / √m`, so that every molecule
+    /// starts with the same kinetic energy; drawn when the job is built, once the
+    /// positions and masses are known.
     pub fn random_vel(mut self, temperature: Real) -> Self {
-        let n_mol = self.0.state.get_pos().len();
-        let vel_mag = (temperature * (D as Real) * (1. - 1. / (n_mol as Real))).sqrt();
-        crate::initial_state::randomize_vectors(&mut self.0.state.get_vel(), vel_mag);
-        let sum = self.0.vel_sum();
-        let k = -1. / n_mol as Real;
-        crate::initial_state::shift_vectors(&mut self.0.state.get_vel(), &(k * sum));
+        self.temperature = Some(temperature);
         self
     }
 
-    pub fn job(self) -> Job<D> {
-        self.0
+    /// Values of every particle type spread over the molecules by their types.
+    fn per_molecule(&self, values: &[Real], what: &str) -> Vec<Real> {
+        let n_mol = self.job.state.get_pos().len();
+        let types = state::or_empty(&self.job.state.get_types()).to_vec();
+        (0..n_mol)
+            .map(|j| {
+                let t = types.get(j).copied().unwrap_or_default();
+                *values.get(t).unwrap_or_else(|| {
+                    panic!("particle type {t} has no {what}, {} given", values.len())
+                })
+            })
+            .collect()
+    }
+
+    fn randomize_vel(&mut self, temperature: Real) {
+        let n_mol = self.job.state.get_pos().len();
+        let vel_mag = (temperature * (D as Real) * (1. - 1. / (n_mol as Real))).sqrt();
+        crate::initial_state::randomize_vectors(&mut self.job.state.get_vel(), vel_mag);
+        let masses = state::or_empty(&self.job.state.get_masses()).to_vec();
+        let mut total_mass = 0.;
+        for (j, velocity) in self.job.state.get_vel().iter_mut().enumerate() {
+            let m = state::mass(&masses, j);
+            *velocity = (1. / m.sqrt()) * &*velocity;
+            total_mass += m;
+        }
+        let k = -1. / total_mass;
+        let momentum = self.job.momentum();
+        crate::initial_state::shift_vectors(&mut self.job.state.get_vel(), &(k * momentum));
+    }
+
+    pub fn job(mut self) -> Job<D> {
+        if let Some(masses) = self.type_masses.take() {
+            let masses = self.per_molecule(&masses, "mass");
+            self = self.masses(masses);
+        }
+        if let Some(charges) = self.type_charges.take() {
+            let charges = self.per_molecule(&charges, "charge");
+            self = self.charges(charges);
+        }
+        if let Some(temperature) = self.temperature {
+            self.randomize_vel(temperature);
+        }
+        match (self.potential, self.terms) {
+            (Some(potential), Some(terms)) => {
+                self.job.potential = Box::new(terms.first_term(potential))
+            }
+            (Some(potential), None) => self.job.potential = potential,
+            (None, Some(terms)) => self.job.potential = Box::new(terms),
+            (None, None) => {}
+        }
+        if let Err(message) = self.job.props.check(self.job.boundaries.as_ref()) {
+            panic!("{message}");
+        }
+        self.job
     }
 }
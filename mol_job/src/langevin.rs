@@ -0,0 +1,209 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    boundaries::BoundaryConditions, integrator::Integrator, potential::PotentialEnergy, state,
+    verlet,
+};
+use d_vector::{DVector, Real};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::StandardNormal;
+use std::{ops::AddAssign, sync::Mutex};
+
+fn gaussian_vector<const D: usize>(rng: &mut StdRng) -> DVector<D> {
+    let mut components = [0 as Real; D];
+    for c in components.iter_mut() {
+        *c = rng.sample(StandardNormal);
+    }
+    DVector::from(components)
+}
+
+/// Underdamped Langevin dynamics with the BAOAB splitting
+/// (kick, drift, Ornstein–Uhlenbeck, drift, kick).
+#[derive(Debug)]
+pub struct Langevin {
+    temperature: Real,
+    friction: Real,
+    rng: Mutex<StdRng>,
+}
+
+impl Langevin {
+    pub fn new(temperature: Real, friction: Real) -> Self {
+        Self {
+            temperature,
+            friction,
+            rng: Mutex::new(StdRng::from_entropy()),
+        }
+    }
+
+    pub fn seed(self, seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            ..self
+        }
+    }
+}
+
+impl<const D: usize> Integrator<D> for Langevin {
+    fn single_step(
+        &self,
+        delta_t: Real,
+        pos: &mut [DVector<D>],
+        vel: &mut [DVector<D>],
+        acc: &mut [DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        potential_energy: &dyn PotentialEnergy<D>,
+    ) {
+        assert_eq!(pos.len(), vel.len());
+        assert_eq!(vel.len(), acc.len());
+        let half_delta_t = delta_t / 2.;
+        let c1 = (-self.friction * delta_t).exp();
+        let c2 = ((1. - c1 * c1) * self.temperature).sqrt();
+        let mut rng = self.rng.lock().unwrap();
+        for (j, ((position, velocity), acceleration)) in pos
+            .iter_mut()
+            .zip(vel.iter_mut())
+            .zip(acc.iter())
+            .enumerate()
+        {
+            velocity.add_assign(half_delta_t * acceleration);
+            position.add_assign(half_delta_t * &*velocity);
+            let noise = c2 / state::mass(masses, j).sqrt();
+            *velocity = c1 * &*velocity + noise * gaussian_vector(&mut rng);
+            position.add_assign(half_delta_t * &*velocity);
+        }
+        verlet::apply_boundary_conditions(boundaries, pos);
+        verlet::compute_accelerations(potential_energy, pos, acc, masses, boundaries);
+        for (velocity, acceleration) in vel.iter_mut().zip(acc.iter()) {
+            velocity.add_assign(half_delta_t * acceleration);
+        }
+    }
+}
+
+/// Overdamped (Brownian) dynamics: `dx = F / γ dt + sqrt(2 T dt / γ) ξ`.
+/// Momenta relax instantly in this limit, so velocities are redrawn every step
+/// from the Maxwell distribution at the bath temperature.
+#[derive(Debug)]
+pub struct Brownian {
+    temperature: Real,
+    friction: Real,
+    rng: Mutex<StdRng>,
+}
+
+impl Brownian {
+    pub fn new(temperature: Real, friction: Real) -> Self {
+        Self {
+            temperature,
+            friction,
+            rng: Mutex::new(StdRng::from_entropy()),
+        }
+    }
+
+    pub fn seed(self, seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            ..self
+        }
+    }
+}
+
+impl<const D: usize> Integrator<D> for Brownian {
+    fn single_step(
+        &self,
+        delta_t: Real,
+        pos: &mut [DVector<D>],
+        vel: &mut [DVector<D>],
+        acc: &mut [DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        potential_energy: &dyn PotentialEnergy<D>,
+    ) {
+        assert_eq!(pos.len(), vel.len());
+        assert_eq!(vel.len(), acc.len());
+        let drift = delta_t / self.friction;
+        let noise = (2. * self.temperature * drift).sqrt();
+        let thermal = self.temperature.sqrt();
+        let mut rng = self.rng.lock().unwrap();
+        for (j, ((position, velocity), acceleration)) in pos
+            .iter_mut()
+            .zip(vel.iter_mut())
+            .zip(acc.iter())
+            .enumerate()
+        {
+            let m = state::mass(masses, j);
+            position.add_assign((drift * m) * acceleration);
+            position.add_assign(noise * gaussian_vector(&mut rng));
+            *velocity = (thermal / m.sqrt()) * gaussian_vector(&mut rng);
+        }
+        verlet::apply_boundary_conditions(boundaries, pos);
+        verlet::compute_accelerations(potential_energy, pos, acc, masses, boundaries);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        boundaries::Region,
+        initial_state,
+        job::{Job, JobSetup},
+        lennard_jones::LennardJones,
+        test_support::Spring,
+    };
+
+    /// Mean `x²` and `m v²`, both equal to the temperature at equilibrium.
+    fn spring_moments(integrator: &dyn Integrator<1>, delta_t: Real, mass: Real) -> (Real, Real) {
+        let region = Region::new([1000.]);
+        let spring = Spring::default();
+        let n_mol = 200;
+        let mut pos = vec![DVector::default(); n_mol];
+        let mut vel = vec![DVector::default(); n_mol];
+        let mut acc = vec![DVector::default(); n_mol];
+        let masses = vec![mass; n_mol];
+        let (mut xx, mut vv) = (0., 0.);
+        let samples = 2000;
+        for step in 0..(samples + 500) {
+            integrator.single_step(
+                delta_t, &mut pos, &mut vel, &mut acc, &masses, &region, &spring,
+            );
+            if step >= 500 {
+                xx += pos.iter().map(|x| x.square_length()).sum::<Real>();
+                vv += vel.iter().map(|v| v.square_length()).sum::<Real>();
+            }
+        }
+        let norm = (samples * n_mol) as Real;
+        (xx / norm, mass * vv / norm)
+    }
+
+    #[test]
+    fn equipartition_in_harmonic_well() {
+        let (xx, vv) = spring_moments(&Langevin::new(0.7, 1.).seed(1), 0.05, 1.);
+        assert!((xx - 0.7).abs() < 0.05);
+        assert!((vv - 0.7).abs() < 0.05);
+        let (xx, vv) = spring_moments(&Langevin::new(0.7, 1.).seed(4), 0.05, 4.);
+        assert!((xx - 0.7).abs() < 0.05);
+        assert!((vv - 0.7).abs() < 0.05);
+        let (xx, vv) = spring_moments(&Brownian::new(0.7, 1.).seed(2), 0.01, 1.);
+        assert!((xx - 0.7).abs() < 0.05);
+        assert!((vv - 0.7).abs() < 0.05);
+    }
+
+    #[test]
+    fn langevin_fluid_temperature() {
+        let (boundaries, pos) = initial_state::cubic_lattice(216, 0.8);
+        let mut job: Job<3> = JobSetup::build()
+            .delta_t(0.005)
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .potential(LennardJones::new((2 as Real).powf(1. / 6.)))
+            .integrator(Langevin::new(1.2, 2.).seed(3))
+            .job();
+        job.run(300);
+        let mut sum = 0.;
+        for _ in 0..2000 {
+            job.run(1);
+            sum += job.temperature();
+        }
+        assert!((sum / 2000. - 1.2).abs() < 0.05);
+    }
+}
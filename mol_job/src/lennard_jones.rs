@@ -1,82 +1,224 @@
 #![allow(unused, dead_code)]
 
-use std::sync::atomic::Ordering;
-use crate::{boundaries::BoundaryConditions, potential::PotentialEnergy};
-use d_vector::{reset_array, DVector, Real};
-use atomic_float::AtomicF32;
-
-#[derive(Debug)]
-pub struct LennardJones {
-    r_cut: Real,
-    u_sum: AtomicF32,
-    v_sum: AtomicF32,
+use crate::{
+    cutoff::{self, Cutoff},
+    pair::{PairPotential, Pairwise, Parameterized},
+    species::{PairParameters, PairTable},
+};
+use d_vector::Real;
+
+/// `u = 4 ε ((σ / r)^12 - (σ / r)^6)` within `r_cut`.
+#[derive(Debug, Clone, Copy)]
+pub struct LennardJonesLaw {
+    pub sigma: Real,
+    pub epsilon: Real,
+    pub r_cut: Real,
 }
 
-impl Default for LennardJones {
-    fn default() -> Self {
-        Self {
-            r_cut: 2.5,
-            u_sum: AtomicF32::new(0.0),
-            v_sum: AtomicF32::new(0.0),
-        }
+impl PairPotential for LennardJonesLaw {
+    fn energy(&self, rr: Real) -> Real {
+        let sr2 = self.sigma * self.sigma / rr;
+        let sr6 = sr2 * sr2 * sr2;
+        4. * self.epsilon * sr6 * (sr6 - 1.)
     }
-}
 
-impl<const D: usize> PotentialEnergy<D> for LennardJones {
-    fn compute_forces(
-        &self,
-        pos: &[DVector<D>],
-        acc: &mut [DVector<D>],
-        boundaries: &dyn BoundaryConditions<D>,
-    ) {
-        let n_mol = pos.len();
-        assert_eq!(n_mol, acc.len());
-
-        let rr_cut = self.r_cut * self.r_cut;
-        reset_array(acc);
-        let mut u_sum = 0 as Real;
-        let mut v_sum = 0 as Real;
-
-        if n_mol > 0 {
-            for j1 in 0..(n_mol - 1) {
-                for j2 in (j1 + 1)..n_mol {
-                    let mut dr = &pos[j1] - &pos[j2];
-                    boundaries.wrap(&mut dr);
-                    let rr = dr.square_length();
-                    if rr < rr_cut {
-                        let rri = 1. / rr;
-                        let rri3 = rri * rri * rri;
-
-                        let force_value = 48. * rri3 * (rri3 - 0.5) * rri;
-                        let force = force_value * dr;
-
-                        acc[j1] += &force;
-                        acc[j2] -= &force;
-
-                        u_sum += 4. * rri3 * (rri3 - 1.) + 1.;
-                        v_sum += force_value * rr;
-                    }
-                }
-            }
-        }
-        self.u_sum.store(u_sum, Ordering::SeqCst);
-        self.v_sum.store(v_sum, Ordering::SeqCst);
+    fn force_over_r(&self, rr: Real) -> Real {
+        let sr2 = self.sigma * self.sigma / rr;
+        let sr6 = sr2 * sr2 * sr2;
+        48. * self.epsilon * sr6 * (sr6 - 0.5) / rr
     }
 
-    fn u_sum(&self) -> Real {
-        self.u_sum.load(Ordering::SeqCst)
+    fn r_cut(&self) -> Real {
+        self.r_cut
     }
 
-    fn virial_sum(&self) -> Real {
-        self.v_sum.load(Ordering::SeqCst)
+    fn tail(&self, dim: usize) -> Option<(Real, Real)> {
+        let s6 = self.sigma.powi(6);
+        let (u12, w12) = cutoff::power_tail(4. * self.epsilon * s6 * s6, 12., self.r_cut, dim);
+        let (u6, w6) = cutoff::power_tail(-4. * self.epsilon * s6, 6., self.r_cut, dim);
+        Some((u12 + u6, w12 + w6))
+    }
+}
+
+impl Parameterized for LennardJonesLaw {
+    fn with_parameters(&self, pair: &PairParameters) -> Self {
+        Self {
+            sigma: pair.sigma,
+            epsilon: pair.epsilon,
+            r_cut: pair.r_cut,
+        }
     }
 }
 
+pub type LennardJones = Pairwise<LennardJonesLaw>;
+
 impl LennardJones {
+    /// `σ = ε = 1`, truncated at `r_cut` with pair energies offset by `+ε`, as they
+    /// always were; `.cutoff(..)` picks a shifted or smoothed cutoff instead.
     pub fn new(r_cut: Real) -> Self {
-        Self {
+        Self::with_potential(LennardJonesLaw {
+            sigma: 1.,
+            epsilon: 1.,
             r_cut,
-            ..Default::default()
+        })
+        .cutoff(Cutoff::Offset(1.))
+    }
+}
+
+impl Default for LennardJones {
+    fn default() -> Self {
+        Self::new(2.5)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        boundaries::Region,
+        cells::PairSearch,
+        initial_state,
+        job::{Job, JobSetup},
+        potential::PotentialEnergy,
+        species::Species,
+        test_support,
+    };
+    use d_vector::DVector;
+
+    fn assert_same_forces<const D: usize>(n_mol: usize, density: Real, fast: LennardJones) {
+        let (region, mut pos) = initial_state::cubic_lattice::<D>(n_mol, density);
+        test_support::jitter(&mut pos, 0.2, 7);
+        let mut acc_all = vec![DVector::default(); pos.len()];
+        let mut acc_fast = vec![DVector::default(); pos.len()];
+        let all_pairs = LennardJones::new(2.5).search(PairSearch::AllPairs);
+        PotentialEnergy::<D>::compute_forces(&all_pairs, &pos, &mut acc_all, &region);
+        PotentialEnergy::<D>::compute_forces(&fast, &pos, &mut acc_fast, &region);
+
+        for (a, c) in acc_all.iter().zip(acc_fast.iter()) {
+            assert!((a - c).length() <= 1e-3 * (1. + a.length()));
+        }
+        let u_all = PotentialEnergy::<D>::u_sum(&all_pairs);
+        let u_fast = PotentialEnergy::<D>::u_sum(&fast);
+        assert!((u_all - u_fast).abs() <= 1e-4 * u_all.abs());
+        let v_all = PotentialEnergy::<D>::virial_sum(&all_pairs);
+        let v_fast = PotentialEnergy::<D>::virial_sum(&fast);
+        assert!((v_all - v_fast).abs() <= 1e-4 * v_all.abs());
+    }
+
+    #[test]
+    fn cells_match_all_pairs() {
+        let cells = || LennardJones::new(2.5).search(PairSearch::Cells);
+        assert_same_forces::<3>(1000, 0.8, cells());
+        assert_same_forces::<2>(900, 0.7, cells());
+    }
+
+    #[test]
+    fn neighbor_list_matches_all_pairs() {
+        let listed = || LennardJones::new(2.5).neighbor_list(0.3);
+        assert_same_forces::<3>(1000, 0.8, listed());
+        assert_same_forces::<2>(900, 0.7, listed());
+    }
+
+    #[test]
+    fn mixture_pair_forces() {
+        let species = [
+            Species::default(),
+            Species {
+                sigma: 2.,
+                epsilon: 4.,
+            },
+        ];
+        let mut lj = LennardJones::new(2.5)
+            .cutoff(Cutoff::Shifted)
+            .pair_table(PairTable::lorentz_berthelot(&species, 2.5));
+        PotentialEnergy::<3>::set_types(&mut lj, &[0, 1]);
+        let region = Region::new([20.; 3]);
+        let pos = vec![DVector::from([0., 0., 0.]), DVector::from([1.8, 0., 0.])];
+        let mut acc = vec![DVector::default(); 2];
+        PotentialEnergy::<3>::compute_forces(&lj, &pos, &mut acc, &region);
+        // σ = 1.5, ε = 2, r_c = 3.75
+        let sr6 = (1.5 as Real / 1.8).powi(6);
+        let sc6 = (1.5 as Real / 3.75).powi(6);
+        let u = 2. * 4. * (sr6 * (sr6 - 1.) - sc6 * (sc6 - 1.));
+        let force = 48. * 2. * sr6 * (sr6 - 0.5) / 1.8;
+        assert!((PotentialEnergy::<3>::u_sum(&lj) - u).abs() < 1e-4);
+        assert!((acc[1].components()[0] - force).abs() < 1e-4);
+        assert!((acc[0].components()[0] + force).abs() < 1e-4);
+    }
+
+    #[test]
+    fn kob_andersen_soft_spheres() {
+        let (region, pos) = initial_state::cubic_lattice::<3>(216, 1.2);
+        let types = initial_state::composition(pos.len(), &[0.8, 0.2]);
+        assert_eq!(43, types.iter().filter(|t| **t == 1).count());
+        let wca = (2 as Real).powf(1. / 6.);
+        let mut job: Job<3> = JobSetup::build()
+            .boundaries(region)
+            .init_pos(pos)
+            .types(types)
+            .random_vel(1.)
+            .potential(
+                LennardJones::new(wca)
+                    .cutoff(Cutoff::Shifted)
+                    .pair_table(PairTable::kob_andersen(wca)),
+            )
+            .job();
+        job.run(1);
+        let e0 = job.kinetic_energy() + job.potential_energy();
+        job.run(300);
+        let e1 = job.kinetic_energy() + job.potential_energy();
+        assert!((e1 - e0).abs() < 1e-2 * e0.abs());
+    }
+
+    /// Largest deviation of the total energy per molecule over an NVE run.
+    fn energy_drift(cutoff: Cutoff) -> Real {
+        let (region, pos) = initial_state::cubic_lattice::<3>(216, 0.8);
+        let mut job: Job<3> = JobSetup::build()
+            .boundaries(region)
+            .init_pos(pos)
+            .random_vel(1.)
+            .potential(LennardJones::new(2.5).cutoff(cutoff))
+            .job();
+        job.run(1);
+        let e0 = job.kinetic_energy() + job.potential_energy();
+        let mut result: Real = 0.;
+        for _ in 0..50 {
+            job.run(10);
+            let e = job.kinetic_energy() + job.potential_energy();
+            result = result.max((e - e0).abs() / 216.);
         }
+        result
+    }
+
+    #[test]
+    fn smooth_cutoffs_conserve_energy() {
+        let truncated = energy_drift(Cutoff::Truncated);
+        for cutoff in [Cutoff::ShiftedForce, Cutoff::Switched { from: 0.8 }] {
+            let drift = energy_drift(cutoff);
+            assert!(
+                drift < 0.2 * truncated,
+                "{cutoff:?}: {drift} vs {truncated}"
+            );
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_forces_are_deterministic() {
+        let (region, mut pos) = initial_state::cubic_lattice::<3>(1000, 0.8);
+        test_support::jitter(&mut pos, 0.2, 8);
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let run = || {
+            let lj = LennardJones::new(2.5);
+            let mut acc = vec![DVector::default(); pos.len()];
+            pool.install(|| PotentialEnergy::<3>::compute_forces(&lj, &pos, &mut acc, &region));
+            let u = PotentialEnergy::<3>::u_sum(&lj);
+            let v = PotentialEnergy::<3>::virial_sum(&lj);
+            (acc, u, v)
+        };
+        assert_eq!(run(), run());
     }
 }
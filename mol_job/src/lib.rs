@@ -1,12 +1,38 @@
+pub mod barostat;
 pub mod boundaries;
+pub mod cells;
+pub mod composite;
+pub mod conductivity;
+pub mod correlation;
+pub mod cutoff;
+pub mod diffusion;
+pub mod ewald;
+pub mod forces;
 pub mod initial_state;
+pub mod integrator;
 pub mod job;
+pub mod langevin;
 pub mod lennard_jones;
+pub mod neighbor_list;
+pub mod order;
+pub mod pair;
+pub mod pair_laws;
 pub mod potential;
 pub mod prop;
+pub mod rdf;
+pub mod scattering;
+pub mod soft_sphere;
+pub mod species;
 pub mod state;
+pub mod statistics;
+pub mod structure;
+pub mod tabulated;
+#[cfg(test)]
+mod test_support;
+pub mod thermostat;
 pub mod track;
 pub mod verlet;
+pub mod viscosity;
 
 #[cfg(test)]
 mod tests {
@@ -17,7 +43,7 @@ mod tests {
         use potential::NoInteraction;
         let mut j: Job<3> = JobSetup::build()
             .delta_t(1e-3)
-            .potential(NoInteraction::default())
+            .potential(NoInteraction)
             .job();
         assert_eq!(0, j.run(100));
         assert_eq!(0.1, j.time_now())
@@ -46,10 +72,95 @@ mod tests {
         let mut j: Job<3> = JobSetup::build()
             .boundaries(boundaries)
             .init_pos(pos)
-            .potential(NoInteraction::default())
+            .potential(NoInteraction)
             .job();
         assert_eq!(0, j.run(100));
         assert_eq!(0.5, j.time_now());
         assert!(j.vel_sum().length() < 1e-3);
     }
+
+    #[test]
+    fn neighbor_rebuilds() {
+        use job::{Job, JobSetup};
+        use lennard_jones::LennardJones;
+
+        let (boundaries, pos) = initial_state::cubic_lattice(1000, 0.8);
+        let mut j: Job<3> = JobSetup::build()
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .random_vel(1.)
+            .potential(LennardJones::new(2.5).neighbor_list(0.3))
+            .job();
+        j.run(50);
+        let rebuilds = j.neighbor_rebuilds().unwrap();
+        assert!((1..50).contains(&rebuilds));
+        assert!(j.steps_per_rebuild().unwrap() > 1.);
+    }
+
+    #[test]
+    fn steps_per_rebuild_counts_steps() {
+        use cells::PairSearch;
+        use integrator::ForestRuth;
+        use job::{Job, JobSetup};
+        use lennard_jones::LennardJones;
+
+        let (boundaries, pos) = initial_state::cubic_lattice(1000, 0.8);
+        let mut j: Job<3> = JobSetup::build()
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .random_vel(1.)
+            .integrator(ForestRuth)
+            .potential(
+                LennardJones::new(2.5)
+                    .neighbor_list(0.3)
+                    .search(PairSearch::Cells),
+            )
+            .job();
+        j.run(50);
+        let rebuilds = j.neighbor_rebuilds().unwrap();
+        assert_eq!(
+            50. / rebuilds as d_vector::Real,
+            j.steps_per_rebuild().unwrap()
+        );
+    }
+
+    #[test]
+    fn setup_order_does_not_matter() {
+        use job::{Job, JobSetup};
+        use potential::NoInteraction;
+
+        let (boundaries, pos) = initial_state::cubic_lattice(216, 0.8);
+        let types = initial_state::composition(pos.len(), &[0.5, 0.5]);
+        let j: Job<3> = JobSetup::build()
+            .random_vel(1.)
+            .type_masses(&[1., 4.])
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .types(types)
+            .potential(NoInteraction)
+            .job();
+        assert!(j.momentum().length() < 1e-3);
+        assert!((j.temperature() - 1.).abs() < 0.05);
+    }
+
+    #[test]
+    #[should_panic(expected = "particle type 1 has no mass")]
+    fn type_without_mass() {
+        use job::JobSetup;
+
+        let (boundaries, pos) = initial_state::cubic_lattice(8, 0.8);
+        let types = initial_state::composition(pos.len(), &[0.5, 0.5]);
+        JobSetup::<3>::build()
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .types(types)
+            .type_masses(&[1.])
+            .job();
+    }
+
+    #[test]
+    fn job_is_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<job::Job<3>>();
+    }
 }
@@ -0,0 +1,151 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    boundaries::BoundaryConditions,
+    cells::{self, PairSearch, Pairs},
+};
+use d_vector::{DVector, Real};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Mutex,
+};
+
+/// Verlet neighbor list holding every pair closer than `r_cut + skin`.
+/// The list is rebuilt once some molecule has moved more than `skin / 2`
+/// since the last build.
+#[derive(Debug)]
+pub struct NeighborList {
+    skin: Real,
+    search: PairSearch,
+    pairs: Mutex<Vec<(usize, usize)>>,
+    reference: Mutex<Vec<Real>>,
+    rebuilds: AtomicUsize,
+    updates: AtomicUsize,
+}
+
+impl NeighborList {
+    pub fn new(skin: Real) -> Self {
+        Self {
+            skin,
+            search: PairSearch::default(),
+            pairs: Mutex::new(Vec::new()),
+            reference: Mutex::new(Vec::new()),
+            rebuilds: AtomicUsize::new(0),
+            updates: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn search(mut self, search: PairSearch) -> Self {
+        self.search = search;
+        self
+    }
+
+    pub fn skin(&self) -> Real {
+        self.skin
+    }
+
+    pub fn rebuilds(&self) -> usize {
+        self.rebuilds.load(Ordering::SeqCst)
+    }
+
+    /// Number of times the pairs were handed out, i.e. force evaluations.
+    pub fn updates(&self) -> usize {
+        self.updates.load(Ordering::SeqCst)
+    }
+
+    pub fn for_each_pair<const D: usize>(
+        &self,
+        pos: &[DVector<D>],
+        boundaries: &dyn BoundaryConditions<D>,
+        r_cut: Real,
+        f: impl FnMut(usize, usize),
+    ) {
+        self.pairs(pos, boundaries, r_cut).for_each_pair(f)
+    }
+
+    /// Brings the list up to date with `pos` and hands out its pairs.
+    pub fn pairs<const D: usize>(
+        &self,
+        pos: &[DVector<D>],
+        boundaries: &dyn BoundaryConditions<D>,
+        r_cut: Real,
+    ) -> Pairs<'_, D> {
+        self.updates.fetch_add(1, Ordering::SeqCst);
+        if self.need_rebuild(pos, boundaries) {
+            self.rebuild(pos, boundaries, r_cut);
+        }
+        Pairs::Listed(self.pairs.lock().unwrap())
+    }
+
+    fn need_rebuild<const D: usize>(
+        &self,
+        pos: &[DVector<D>],
+        boundaries: &dyn BoundaryConditions<D>,
+    ) -> bool {
+        let reference = self.reference.lock().unwrap();
+        if reference.len() != D * pos.len() {
+            return true;
+        }
+        let limit = 0.25 * self.skin * self.skin;
+        pos.iter()
+            .zip(reference.chunks_exact(D))
+            .any(|(position, old)| {
+                let mut dr = position - &DVector::from(<[Real; D]>::try_from(old).unwrap());
+                boundaries.wrap(&mut dr);
+                dr.square_length() > limit
+            })
+    }
+
+    fn rebuild<const D: usize>(
+        &self,
+        pos: &[DVector<D>],
+        boundaries: &dyn BoundaryConditions<D>,
+        r_cut: Real,
+    ) {
+        let r_list = r_cut + self.skin;
+        let rr_list = r_list * r_list;
+        let mut pairs = self.pairs.lock().unwrap();
+        pairs.clear();
+        cells::for_each_pair(pos, boundaries, r_list, self.search, |j1, j2| {
+            let mut dr = &pos[j1] - &pos[j2];
+            boundaries.wrap(&mut dr);
+            if dr.square_length() < rr_list {
+                pairs.push((j1, j2));
+            }
+        });
+        let mut reference = self.reference.lock().unwrap();
+        reference.clear();
+        for position in pos.iter() {
+            reference.extend_from_slice(position.components());
+        }
+        self.rebuilds.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::boundaries::Region;
+
+    #[test]
+    fn rebuild_after_half_skin() {
+        let region = Region::new([10., 10.]);
+        let mut pos = vec![DVector::from([0., 0.]), DVector::from([2.8, 0.])];
+        let list = NeighborList::new(0.4);
+        let mut pairs = 0;
+        list.for_each_pair(&pos, &region, 2.5, |_, _| pairs += 1);
+        assert_eq!(1, pairs);
+        assert_eq!(1, list.rebuilds());
+
+        pos[0] = DVector::from([0.15, 0.]);
+        list.for_each_pair(&pos, &region, 2.5, |_, _| {});
+        assert_eq!(1, list.rebuilds());
+
+        pos[0] = DVector::from([-0.25, 0.]);
+        let mut pairs = 0;
+        list.for_each_pair(&pos, &region, 2.5, |_, _| pairs += 1);
+        assert_eq!(0, pairs);
+        assert_eq!(2, list.rebuilds());
+        assert_eq!(3, list.updates());
+    }
+}
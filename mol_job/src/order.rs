@@ -0,0 +1,588 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    boundaries::BoundaryConditions,
+    cells::{self, PairSearch},
+    potential::PotentialEnergy,
+    prop::{Prop, PropSummary, Props},
+};
+use d_vector::{DVector, Real};
+use num_complex::Complex64;
+use std::{
+    cell::{Cell, RefCell},
+    f64::consts::PI,
+};
+
+/// Separations `r_k - r_j` from every molecule `j` to its neighbors `k` within `r_cut`.
+pub fn bonds<const D: usize>(
+    pos: &[DVector<D>],
+    boundaries: &dyn BoundaryConditions<D>,
+    r_cut: Real,
+) -> Vec<Vec<(usize, DVector<D>)>> {
+    let mut result = vec![Vec::new(); pos.len()];
+    let rr_cut = r_cut * r_cut;
+    cells::for_each_pair(pos, boundaries, r_cut, PairSearch::Cells, |j1, j2| {
+        let mut dr = &pos[j2] - &pos[j1];
+        boundaries.wrap(&mut dr);
+        if dr.square_length() < rr_cut {
+            result[j2].push((j1, -1. * &dr));
+            result[j1].push((j2, dr));
+        }
+    });
+    result
+}
+
+fn factorial(n: i64) -> f64 {
+    (1..=n).map(|k| k as f64).product()
+}
+
+/// `Y_lm(r̂)` for `m = -l..=l`, with the Condon–Shortley phase.
+pub fn spherical_harmonics(l: usize, dr: &DVector<3>) -> Vec<Complex64> {
+    let [x, y, z] = dr.components().map(|c| c as f64);
+    let r = (x * x + y * y + z * z).sqrt();
+    let cos_theta = if r > 0. { z / r } else { 1. };
+    let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+    let phi = y.atan2(x);
+    let l = l as i64;
+    let mut result = vec![Complex64::default(); (2 * l + 1) as usize];
+    for m in 0..=l {
+        // associated Legendre P_l^m by upward recursion in l
+        let mut p_mm = 1.;
+        for k in 0..m {
+            p_mm *= -((2 * k + 1) as f64) * sin_theta;
+        }
+        let mut p = p_mm;
+        if l > m {
+            let mut p_prev = p_mm;
+            p = cos_theta * (2 * m + 1) as f64 * p_mm;
+            for ll in (m + 2)..=l {
+                let next = ((2 * ll - 1) as f64 * cos_theta * p - (ll + m - 1) as f64 * p_prev)
+                    / (ll - m) as f64;
+                p_prev = p;
+                p = next;
+            }
+        }
+        let norm = ((2 * l + 1) as f64 / (4. * PI) * factorial(l - m) / factorial(l + m)).sqrt();
+        let y = Complex64::from_polar(norm * p, m as f64 * phi);
+        result[(l + m) as usize] = y;
+        let sign = if m % 2 == 0 { 1. } else { -1. };
+        result[(l - m) as usize] = sign * y.conj();
+    }
+    result
+}
+
+/// Wigner 3j symbol by the Racah formula.
+pub fn wigner_3j(l1: i64, l2: i64, l3: i64, m1: i64, m2: i64, m3: i64) -> f64 {
+    if m1 + m2 + m3 != 0 || l3 < (l1 - l2).abs() || l3 > l1 + l2 {
+        return 0.;
+    }
+    if m1.abs() > l1 || m2.abs() > l2 || m3.abs() > l3 {
+        return 0.;
+    }
+    let triangle = factorial(l1 + l2 - l3) * factorial(l1 - l2 + l3) * factorial(-l1 + l2 + l3)
+        / factorial(l1 + l2 + l3 + 1);
+    let front = (triangle
+        * factorial(l1 + m1)
+        * factorial(l1 - m1)
+        * factorial(l2 + m2)
+        * factorial(l2 - m2)
+        * factorial(l3 + m3)
+        * factorial(l3 - m3))
+    .sqrt();
+    let k_min = 0.max(l2 - l3 - m1).max(l1 - l3 + m2);
+    let k_max = (l1 + l2 - l3).min(l1 - m1).min(l2 + m2);
+    let mut sum = 0.;
+    for k in k_min..=k_max {
+        let sign = if k % 2 == 0 { 1. } else { -1. };
+        sum += sign
+            / (factorial(k)
+                * factorial(l3 - l2 + k + m1)
+                * factorial(l3 - l1 + k - m2)
+                * factorial(l1 + l2 - l3 - k)
+                * factorial(l1 - k - m1)
+                * factorial(l2 - k + m2));
+    }
+    let sign = if (l1 - l2 - m3) % 2 == 0 { 1. } else { -1. };
+    sign * front * sum
+}
+
+fn norm_sqr(qlm: &[Complex64]) -> f64 {
+    qlm.iter().map(|q| q.norm_sqr()).sum()
+}
+
+/// `q_l = sqrt(4π / (2l + 1) Σ_m |q_lm|²)`.
+pub fn q_l(qlm: &[Complex64]) -> Real {
+    let l = (qlm.len() - 1) / 2;
+    (4. * PI / (2 * l + 1) as f64 * norm_sqr(qlm)).sqrt() as Real
+}
+
+/// `ŵ_l = Σ 3j(l l l; m1 m2 m3) q_lm1 q_lm2 q_lm3 / (Σ_m |q_lm|²)^(3/2)`.
+pub fn w_l(qlm: &[Complex64]) -> Real {
+    let l = ((qlm.len() - 1) / 2) as i64;
+    let norm = norm_sqr(qlm);
+    if norm == 0. {
+        return 0.;
+    }
+    let mut sum = Complex64::default();
+    for m1 in -l..=l {
+        for m2 in (-l).max(-l - m1)..=l.min(l - m1) {
+            let m3 = -m1 - m2;
+            sum += wigner_3j(l, l, l, m1, m2, m3)
+                * qlm[(m1 + l) as usize]
+                * qlm[(m2 + l) as usize]
+                * qlm[(m3 + l) as usize];
+        }
+    }
+    (sum.re / norm.powf(1.5)) as Real
+}
+
+/// Bond-orientational averages `q_lm(j) = <Y_lm(r̂_jk)>_k` over the neighbors of every molecule.
+pub fn local_qlm(bonds: &[Vec<(usize, DVector<3>)>], l: usize) -> Vec<Vec<Complex64>> {
+    bonds
+        .iter()
+        .map(|neighbors| {
+            let mut qlm = vec![Complex64::default(); 2 * l + 1];
+            for (_, dr) in neighbors.iter() {
+                for (q, y) in qlm.iter_mut().zip(spherical_harmonics(l, dr)) {
+                    *q += y;
+                }
+            }
+            if !neighbors.is_empty() {
+                for q in qlm.iter_mut() {
+                    *q /= neighbors.len() as f64;
+                }
+            }
+            qlm
+        })
+        .collect()
+}
+
+/// Bond-weighted average of the local `q_lm`, giving the global order parameter.
+fn global_qlm(bonds: &[Vec<(usize, DVector<3>)>], local: &[Vec<Complex64>]) -> Vec<Complex64> {
+    let mut result = vec![Complex64::default(); local.first().map_or(0, |q| q.len())];
+    let mut n_bonds = 0;
+    for (neighbors, qlm) in bonds.iter().zip(local.iter()) {
+        n_bonds += neighbors.len();
+        for (r, q) in result.iter_mut().zip(qlm.iter()) {
+            *r += neighbors.len() as f64 * q;
+        }
+    }
+    if n_bonds > 0 {
+        for r in result.iter_mut() {
+            *r /= n_bonds as f64;
+        }
+    }
+    result
+}
+
+fn find(parent: &mut [usize], mut j: usize) -> usize {
+    while parent[j] != j {
+        parent[j] = parent[parent[j]];
+        j = parent[j];
+    }
+    j
+}
+
+/// Size of the largest group of `members` linked through `bonds`.
+pub fn largest_cluster<const D: usize>(
+    bonds: &[Vec<(usize, DVector<D>)>],
+    members: &[bool],
+) -> usize {
+    let mut parent: Vec<_> = (0..bonds.len()).collect();
+    for (j, neighbors) in bonds.iter().enumerate() {
+        for (k, _) in neighbors.iter() {
+            if members[j] && members[*k] {
+                let (a, b) = (find(&mut parent, j), find(&mut parent, *k));
+                parent[a] = b;
+            }
+        }
+    }
+    let mut sizes = vec![0; bonds.len()];
+    for j in 0..bonds.len() {
+        if members[j] {
+            sizes[find(&mut parent, j)] += 1;
+        }
+    }
+    sizes.into_iter().max().unwrap_or(0)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct OrderAnalysis {
+    pub q4: Vec<Real>,
+    pub q6: Vec<Real>,
+    pub global_q4: Real,
+    pub global_q6: Real,
+    pub global_w6: Real,
+    pub solid: Vec<bool>,
+    pub largest_cluster: usize,
+}
+
+/// Steinhardt `q4`, `q6` and `ŵ6` from neighbors within `r_cut`. A molecule is solid-like
+/// in the ten Wolde–Frenkel sense when at least `n_min` of its bonds are connected,
+/// `q̂6(j) · q̂6(k)* > d_min`; solid-like neighbors form the crystalline clusters.
+#[derive(Debug)]
+pub struct BondOrder {
+    r_cut: Real,
+    d_min: Real,
+    n_min: usize,
+    step_avg: usize,
+    step_count: Cell<usize>,
+    q4: RefCell<Prop>,
+    q6: RefCell<Prop>,
+    w6: RefCell<Prop>,
+    solid_fraction: RefCell<Prop>,
+    largest_cluster: RefCell<Prop>,
+    last: RefCell<Option<PropSummary>>,
+}
+
+impl BondOrder {
+    pub fn new(r_cut: Real) -> Self {
+        Self {
+            r_cut,
+            d_min: 0.5,
+            n_min: 7,
+            step_avg: 100,
+            step_count: Cell::new(0),
+            q4: RefCell::default(),
+            q6: RefCell::default(),
+            w6: RefCell::default(),
+            solid_fraction: RefCell::default(),
+            largest_cluster: RefCell::default(),
+            last: RefCell::new(None),
+        }
+    }
+
+    pub fn connections(mut self, d_min: Real, n_min: usize) -> Self {
+        self.d_min = d_min;
+        self.n_min = n_min;
+        self
+    }
+
+    pub fn step_avg(mut self, steps: usize) -> Self {
+        self.step_avg = steps.max(1);
+        self
+    }
+
+    pub fn analyze(
+        &self,
+        pos: &[DVector<3>],
+        boundaries: &dyn BoundaryConditions<3>,
+    ) -> OrderAnalysis {
+        let bonds = bonds(pos, boundaries, self.r_cut);
+        let q4lm = local_qlm(&bonds, 4);
+        let q6lm = local_qlm(&bonds, 6);
+        let global_q4lm = global_qlm(&bonds, &q4lm);
+        let global_q6lm = global_qlm(&bonds, &q6lm);
+
+        let norms: Vec<_> = q6lm.iter().map(|q| norm_sqr(q).sqrt()).collect();
+        let solid: Vec<_> = bonds
+            .iter()
+            .enumerate()
+            .map(|(j, neighbors)| {
+                let connected = neighbors
+                    .iter()
+                    .filter(|(k, _)| {
+                        let norm = norms[j] * norms[*k];
+                        norm > 0. && {
+                            let dot: f64 = q6lm[j]
+                                .iter()
+                                .zip(q6lm[*k].iter())
+                                .map(|(a, b)| (a * b.conj()).re)
+                                .sum();
+                            dot / norm > self.d_min as f64
+                        }
+                    })
+                    .count();
+                connected >= self.n_min
+            })
+            .collect();
+
+        OrderAnalysis {
+            q4: q4lm.iter().map(|q| q_l(q)).collect(),
+            q6: q6lm.iter().map(|q| q_l(q)).collect(),
+            global_q4: q_l(&global_q4lm),
+            global_q6: q_l(&global_q6lm),
+            global_w6: w_l(&global_q6lm),
+            largest_cluster: largest_cluster(&bonds, &solid),
+            solid,
+        }
+    }
+
+    fn props(&self) -> [(&'static str, &RefCell<Prop>); 5] {
+        [
+            ("q4", &self.q4),
+            ("q6", &self.q6),
+            ("w6", &self.w6),
+            ("solid_fraction", &self.solid_fraction),
+            ("largest_cluster", &self.largest_cluster),
+        ]
+    }
+}
+
+impl Props<3> for BondOrder {
+    fn reset(&self) {
+        for (_, prop) in self.props() {
+            prop.borrow_mut().reset();
+        }
+    }
+
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<3>,
+        pos: &[DVector<3>],
+        vel: &[DVector<3>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<3>,
+        unwrapped: &[DVector<3>],
+        delta_t: Real,
+        time: Real,
+    ) {
+        let analysis = self.analyze(pos, boundaries);
+        let n_solid = analysis.solid.iter().filter(|s| **s).count();
+        let values = [
+            analysis.global_q4,
+            analysis.global_q6,
+            analysis.global_w6,
+            n_solid as Real / pos.len().max(1) as Real,
+            analysis.largest_cluster as Real,
+        ];
+        for ((_, prop), val) in self.props().iter().zip(values) {
+            prop.borrow_mut().val = val;
+        }
+    }
+
+    fn accum_props(&self) {
+        for (_, prop) in self.props() {
+            prop.borrow_mut().accum();
+        }
+    }
+
+    fn need_avg(&self, step_count: usize) -> bool {
+        self.step_count.set(step_count);
+        step_count.is_multiple_of(self.step_avg)
+    }
+
+    fn avg_props(&self) {
+        let entries = self
+            .props()
+            .iter()
+            .map(|(name, prop)| prop.borrow().entry(name))
+            .collect();
+        *self.last.borrow_mut() = Some(PropSummary {
+            step_count: self.step_count.get(),
+            entries,
+            tables: Vec::new(),
+        });
+    }
+
+    fn summary(&self) -> Option<PropSummary> {
+        self.last.borrow().clone()
+    }
+}
+
+/// Local `|ψ6(j)| = |<exp(6iθ_jk)>_k|` over the neighbors within `r_cut`,
+/// and the global `|Ψ6|`, averaged over all bonds.
+pub fn hexatic_order(
+    pos: &[DVector<2>],
+    boundaries: &dyn BoundaryConditions<2>,
+    r_cut: Real,
+) -> (Vec<Real>, Real) {
+    let bonds = bonds(pos, boundaries, r_cut);
+    let mut global = Complex64::default();
+    let mut n_bonds = 0;
+    let local = bonds
+        .iter()
+        .map(|neighbors| {
+            let psi: Complex64 = neighbors
+                .iter()
+                .map(|(_, dr)| {
+                    let [x, y] = dr.components().map(|c| c as f64);
+                    Complex64::from_polar(1., 6. * y.atan2(x))
+                })
+                .sum();
+            global += psi;
+            n_bonds += neighbors.len();
+            if neighbors.is_empty() {
+                0.
+            } else {
+                (psi.norm() / neighbors.len() as f64) as Real
+            }
+        })
+        .collect();
+    let global = if n_bonds > 0 {
+        (global.norm() / n_bonds as f64) as Real
+    } else {
+        0.
+    };
+    (local, global)
+}
+
+/// Hexatic order of a two-dimensional system: global `|Ψ6|` and the mean local `|ψ6|`.
+#[derive(Debug)]
+pub struct Hexatic {
+    r_cut: Real,
+    step_avg: usize,
+    step_count: Cell<usize>,
+    psi6: RefCell<Prop>,
+    local_psi6: RefCell<Prop>,
+    last: RefCell<Option<PropSummary>>,
+}
+
+impl Hexatic {
+    pub fn new(r_cut: Real) -> Self {
+        Self {
+            r_cut,
+            step_avg: 100,
+            step_count: Cell::new(0),
+            psi6: RefCell::default(),
+            local_psi6: RefCell::default(),
+            last: RefCell::new(None),
+        }
+    }
+
+    pub fn step_avg(mut self, steps: usize) -> Self {
+        self.step_avg = steps.max(1);
+        self
+    }
+
+    fn props(&self) -> [(&'static str, &RefCell<Prop>); 2] {
+        [("psi6", &self.psi6), ("local_psi6", &self.local_psi6)]
+    }
+}
+
+impl Props<2> for Hexatic {
+    fn reset(&self) {
+        for (_, prop) in self.props() {
+            prop.borrow_mut().reset();
+        }
+    }
+
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<2>,
+        pos: &[DVector<2>],
+        vel: &[DVector<2>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<2>,
+        unwrapped: &[DVector<2>],
+        delta_t: Real,
+        time: Real,
+    ) {
+        let (local, global) = hexatic_order(pos, boundaries, self.r_cut);
+        let mean = local.iter().sum::<Real>() / local.len().max(1) as Real;
+        for ((_, prop), val) in self.props().iter().zip([global, mean]) {
+            prop.borrow_mut().val = val;
+        }
+    }
+
+    fn accum_props(&self) {
+        for (_, prop) in self.props() {
+            prop.borrow_mut().accum();
+        }
+    }
+
+    fn need_avg(&self, step_count: usize) -> bool {
+        self.step_count.set(step_count);
+        step_count.is_multiple_of(self.step_avg)
+    }
+
+    fn avg_props(&self) {
+        let entries = self
+            .props()
+            .iter()
+            .map(|(name, prop)| prop.borrow().entry(name))
+            .collect();
+        *self.last.borrow_mut() = Some(PropSummary {
+            step_count: self.step_count.get(),
+            entries,
+            tables: Vec::new(),
+        });
+    }
+
+    fn summary(&self) -> Option<PropSummary> {
+        self.last.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{boundaries::Region, initial_state, test_support};
+
+    fn fcc_lattice(cells: usize, a: Real) -> (Region<3>, Vec<DVector<3>>) {
+        let size = cells as Real * a;
+        let basis = [[0., 0., 0.], [0.5, 0.5, 0.], [0.5, 0., 0.5], [0., 0.5, 0.5]];
+        let mut pos = Vec::new();
+        for i in 0..cells.pow(3) {
+            let corner = [i % cells, i / cells % cells, i / (cells * cells)];
+            for b in basis.iter() {
+                let mut r = [0.; 3];
+                for k in 0..3 {
+                    r[k] = (corner[k] as Real + b[k] + 0.25) * a - size / 2.;
+                }
+                pos.push(DVector::from(r));
+            }
+        }
+        (Region::new([size; 3]), pos)
+    }
+
+    #[test]
+    fn wigner_symbols() {
+        assert!((wigner_3j(1, 1, 0, 0, 0, 0) + (1. / 3f64).sqrt()).abs() < 1e-12);
+        assert!((wigner_3j(2, 2, 2, 0, 0, 0) + (2. / 35f64).sqrt()).abs() < 1e-12);
+        assert_eq!(0., wigner_3j(1, 1, 1, 1, 1, 1));
+    }
+
+    #[test]
+    fn lattice_order_parameters() {
+        let (region, pos) = initial_state::cubic_lattice::<3>(216, 1.);
+        let order = BondOrder::new(1.2).analyze(&pos, &region);
+        assert!((order.global_q4 - 0.76376).abs() < 1e-3);
+        assert!((order.global_q6 - 0.35355).abs() < 1e-3);
+
+        let (region, pos) = fcc_lattice(4, 1.5);
+        let order = BondOrder::new(1.3).analyze(&pos, &region);
+        assert!((order.global_q4 - 0.19094).abs() < 1e-3);
+        assert!((order.global_q6 - 0.57452).abs() < 1e-3);
+        assert!((order.global_w6 + 0.01316).abs() < 1e-4);
+        assert!((order.q6[0] - 0.57452).abs() < 1e-3);
+        assert!(order.solid.iter().all(|s| *s));
+        assert_eq!(pos.len(), order.largest_cluster);
+    }
+
+    #[test]
+    fn gas_is_liquid_like() {
+        let region = Region::new([10.; 3]);
+        let pos: Vec<_> = test_support::random_pos(800, 10., 9);
+        let order = BondOrder::new(1.3).analyze(&pos, &region);
+        let n_solid = order.solid.iter().filter(|s| **s).count();
+        assert!(n_solid < 40);
+        assert!(order.largest_cluster < 20);
+        assert!(order.global_q6 < 0.1);
+    }
+
+    #[test]
+    fn hexatic_lattices() {
+        let (region, pos) = initial_state::cubic_lattice::<2>(400, 1.);
+        let (local, global) = hexatic_order(&pos, &region, 1.2);
+        assert!(global < 1e-4);
+        assert!(local.iter().all(|psi| *psi < 1e-4));
+
+        let rows = 20;
+        let (a, h) = (1., (3 as Real).sqrt() / 2.);
+        let region = Region::new([rows as Real * a, rows as Real * h]);
+        let pos: Vec<_> = (0..rows * rows)
+            .map(|i| {
+                let (col, row) = (i % rows, i / rows);
+                let x = (col as Real + 0.5 * (row % 2) as Real) * a;
+                DVector::from([
+                    x - region.dimensions()[0] / 2.,
+                    row as Real * h - region.dimensions()[1] / 2.,
+                ])
+            })
+            .collect();
+        let (local, global) = hexatic_order(&pos, &region, 1.2);
+        assert!((global - 1.).abs() < 1e-4);
+        assert!(local.iter().all(|psi| (psi - 1.).abs() < 1e-4));
+    }
+}
@@ -0,0 +1,345 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    boundaries::BoundaryConditions,
+    cells::{PairSearch, Pairs},
+    cutoff::{self, Cutoff, TailCorrection},
+    forces::{self, PairTerm, ParticleSums, StoredSums},
+    neighbor_list::NeighborList,
+    potential::{PotentialEnergy, VirialTensor},
+    rdf,
+    species::{PairParameters, PairTable},
+};
+use d_vector::{reset_array, DVector, Real};
+use std::{
+    fmt::{self, Debug},
+    sync::OnceLock,
+};
+
+/// Radial interaction between one pair of particle types.
+pub trait PairPotential: Debug + Send + Sync {
+    /// Energy at squared distance `rr`, inside the cutoff.
+    fn energy(&self, rr: Real) -> Real;
+    /// `-(du/dr) / r` at squared distance `rr`, inside the cutoff.
+    fn force_over_r(&self, rr: Real) -> Real;
+    /// Distance beyond which the pair does not interact.
+    fn r_cut(&self) -> Real;
+    /// Tail integrals `(I_u, I_w)` beyond the cutoff in `dim` dimensions,
+    /// for laws with an infinite range.
+    fn tail(&self, dim: usize) -> Option<(Real, Real)> {
+        None
+    }
+}
+
+/// Pair laws that take their `σ`, `ε` and cutoff from a `PairTable`.
+pub trait Parameterized: PairPotential + Sized {
+    fn with_parameters(&self, pair: &PairParameters) -> Self;
+}
+
+/// Pair potential given by closures of the squared distance.
+pub struct FnPair {
+    energy: Box<dyn Fn(Real) -> Real + Send + Sync>,
+    force_over_r: Box<dyn Fn(Real) -> Real + Send + Sync>,
+    r_cut: Real,
+}
+
+impl FnPair {
+    pub fn new(
+        r_cut: Real,
+        energy: impl Fn(Real) -> Real + Send + Sync + 'static,
+        force_over_r: impl Fn(Real) -> Real + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            energy: Box::new(energy),
+            force_over_r: Box::new(force_over_r),
+            r_cut,
+        }
+    }
+}
+
+impl Debug for FnPair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FnPair")
+            .field("r_cut", &self.r_cut)
+            .finish_non_exhaustive()
+    }
+}
+
+impl PairPotential for FnPair {
+    fn energy(&self, rr: Real) -> Real {
+        (self.energy)(rr)
+    }
+
+    fn force_over_r(&self, rr: Real) -> Real {
+        (self.force_over_r)(rr)
+    }
+
+    fn r_cut(&self) -> Real {
+        self.r_cut
+    }
+}
+
+/// Sum of a pair potential over all pairs within the cutoff, with one
+/// instance of the potential per pair of particle types.
+#[derive(Debug)]
+pub struct Pairwise<P: PairPotential> {
+    pairs: Vec<P>,
+    n_types: usize,
+    types: Vec<usize>,
+    cutoff: Cutoff,
+    search: PairSearch,
+    skin: Option<Real>,
+    neighbors: OnceLock<NeighborList>,
+    per_particle: bool,
+    sums: StoredSums,
+}
+
+impl<P: PairPotential> Pairwise<P> {
+    /// A single particle type interacting through `potential`.
+    pub fn with_potential(potential: P) -> Self {
+        Self::with_potentials(1, vec![potential])
+    }
+
+    /// One potential per pair of types, row by row; the matrix must be symmetric.
+    pub fn with_potentials(n_types: usize, pairs: Vec<P>) -> Self {
+        assert_eq!(n_types * n_types, pairs.len());
+        Self {
+            pairs,
+            n_types,
+            types: Vec::new(),
+            cutoff: Cutoff::default(),
+            search: PairSearch::default(),
+            skin: None,
+            neighbors: OnceLock::new(),
+            per_particle: false,
+            sums: StoredSums::default(),
+        }
+    }
+
+    /// Potential of types `i` and `j` given by `pair(i, j)`, called with `i <= j`.
+    pub fn per_type(n_types: usize, pair: impl Fn(usize, usize) -> P) -> Self {
+        let mut pairs = Vec::with_capacity(n_types * n_types);
+        for i in 0..n_types {
+            for j in 0..n_types {
+                pairs.push(pair(i.min(j), i.max(j)));
+            }
+        }
+        Self::with_potentials(n_types, pairs)
+    }
+
+    /// Treatment of the cutoff, `Cutoff::Truncated` by default.
+    pub fn cutoff(mut self, cutoff: Cutoff) -> Self {
+        self.cutoff = cutoff;
+        self
+    }
+
+    pub fn search(mut self, search: PairSearch) -> Self {
+        self.search = search;
+        self
+    }
+
+    /// Verlet list with the given skin, built with the pair search on first use.
+    pub fn neighbor_list(mut self, skin: Real) -> Self {
+        self.skin = Some(skin);
+        self
+    }
+
+    /// Keeps per-molecule energies and virials, as needed for the heat flux.
+    pub fn per_particle(mut self, per_particle: bool) -> Self {
+        self.per_particle = per_particle;
+        self
+    }
+
+    pub fn potential(&self, i: usize, j: usize) -> &P {
+        &self.pairs[i * self.n_types + j]
+    }
+
+    fn list(&self) -> Option<&NeighborList> {
+        let skin = self.skin?;
+        Some(
+            self.neighbors
+                .get_or_init(|| NeighborList::new(skin).search(self.search)),
+        )
+    }
+
+    fn max_r_cut(&self) -> Real {
+        self.pairs.iter().fold(0., |r, pair| r.max(pair.r_cut()))
+    }
+}
+
+impl<P: Parameterized> Pairwise<P> {
+    /// Per-type-pair `σ`, `ε` and cutoff, for mixtures; laws with a natural
+    /// range ignore the cutoff.
+    pub fn pair_table(mut self, table: PairTable) -> Self {
+        let n_types = table.n_types();
+        let mut pairs = Vec::with_capacity(n_types * n_types);
+        for i in 0..n_types {
+            for j in 0..n_types {
+                pairs.push(self.pairs[0].with_parameters(table.get(i, j)));
+            }
+        }
+        self.pairs = pairs;
+        self.n_types = n_types;
+        self
+    }
+}
+
+impl<const D: usize, P: PairPotential> PotentialEnergy<D> for Pairwise<P> {
+    fn compute_forces(
+        &self,
+        pos: &[DVector<D>],
+        acc: &mut [DVector<D>],
+        boundaries: &dyn BoundaryConditions<D>,
+    ) {
+        let n_mol = pos.len();
+        assert_eq!(n_mol, acc.len());
+        assert!(self.types.is_empty() || self.types.len() == n_mol);
+
+        let r_cut = self.max_r_cut();
+        let type_of = |j: usize| self.types.get(j).copied().unwrap_or_default();
+        reset_array(acc);
+
+        let pairs = match self.list() {
+            Some(list) => list.pairs(pos, boundaries, r_cut),
+            None => Pairs::new(pos, boundaries, r_cut, self.search),
+        };
+        let mut particles = self.per_particle.then(|| ParticleSums::new(n_mol));
+        let sums = forces::accumulate(&pairs, acc, particles.as_mut(), |j1, j2| {
+            let mut dr = &pos[j1] - &pos[j2];
+            boundaries.wrap(&mut dr);
+            let rr = dr.square_length();
+            let pair = self.potential(type_of(j1), type_of(j2));
+            let r_cut = pair.r_cut();
+            if rr < r_cut * r_cut {
+                let (u, force_value) = self
+                    .cutoff
+                    .apply(rr, r_cut, |rr| (pair.energy(rr), pair.force_over_r(rr)));
+                Some(PairTerm {
+                    force: force_value * &dr,
+                    dr,
+                    u,
+                    virial: force_value * rr,
+                })
+            } else {
+                None
+            }
+        });
+        self.sums.store(&sums);
+        if let Some(particles) = &particles {
+            self.sums.store_particles(particles);
+        }
+    }
+
+    fn set_types(&mut self, types: &[usize]) {
+        assert!(types.iter().all(|t| *t < self.n_types));
+        self.types = types.to_vec();
+    }
+
+    fn u_sum(&self) -> Real {
+        self.sums.u_sum()
+    }
+
+    fn virial_sum(&self) -> Real {
+        self.sums.virial_sum()
+    }
+
+    fn virial_tensor(&self) -> Option<VirialTensor<D>> {
+        self.sums.virial_tensor()
+    }
+
+    fn particle_energies(&self) -> Option<Vec<Real>> {
+        self.sums.particle_energies()
+    }
+
+    fn particle_virials(&self) -> Option<Vec<VirialTensor<D>>> {
+        self.sums.particle_virials()
+    }
+
+    /// Pressure from the tail integrals of the bare law. The energy also restores
+    /// the constant a shifted or offset cutoff takes off each pair inside `r_c`,
+    /// `N ρ / 2 · u_shift · V_ball(r_c)`; `None` for cutoffs that change the force.
+    fn tail_correction(&self, n_mol: usize, volume: Real) -> Option<TailCorrection> {
+        self.pairs[0].tail(D)?;
+        self.cutoff.energy_shift(0.)?;
+        let tail = cutoff::tail_correction(self.n_types, &self.types, n_mol, volume, D, |i, j| {
+            let pair = self.potential(i, j);
+            let (i_u, i_w) = pair.tail(D).unwrap_or_default();
+            let r_cut = pair.r_cut();
+            let shift = self.cutoff.energy_shift(pair.energy(r_cut * r_cut));
+            let ball = rdf::unit_ball_volume(D) * r_cut.powi(D as i32);
+            (i_u + shift.unwrap_or_default() * ball, i_w)
+        });
+        Some(tail)
+    }
+
+    fn neighbors(&self) -> Option<&NeighborList> {
+        self.list()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{boundaries::Region, initial_state, lennard_jones::LennardJones, test_support};
+
+    fn lennard_jones_fn(r_cut: Real) -> FnPair {
+        FnPair::new(
+            r_cut,
+            |rr| {
+                let sr6 = 1. / (rr * rr * rr);
+                4. * sr6 * (sr6 - 1.)
+            },
+            |rr| {
+                let sr6 = 1. / (rr * rr * rr);
+                48. * sr6 * (sr6 - 0.5) / rr
+            },
+        )
+    }
+
+    #[test]
+    fn closure_matches_lennard_jones() {
+        let (region, mut pos) = initial_state::cubic_lattice::<3>(512, 0.8);
+        test_support::jitter(&mut pos, 0.1, 10);
+        let mut acc_fn = vec![DVector::default(); pos.len()];
+        let mut acc_lj = vec![DVector::default(); pos.len()];
+        let closure = Pairwise::with_potential(lennard_jones_fn(2.5)).cutoff(Cutoff::Offset(1.));
+        let lj = LennardJones::new(2.5);
+        PotentialEnergy::<3>::compute_forces(&closure, &pos, &mut acc_fn, &region);
+        PotentialEnergy::<3>::compute_forces(&lj, &pos, &mut acc_lj, &region);
+        for (a, b) in acc_lj.iter().zip(acc_fn.iter()) {
+            assert!((a - b).length() < 1e-4 * (1. + a.length()));
+        }
+        let u_lj = PotentialEnergy::<3>::u_sum(&lj);
+        assert!((PotentialEnergy::<3>::u_sum(&closure) - u_lj).abs() < 1e-5 * u_lj.abs());
+        let v_lj = PotentialEnergy::<3>::virial_sum(&lj);
+        assert!((PotentialEnergy::<3>::virial_sum(&closure) - v_lj).abs() < 1e-5 * v_lj.abs());
+    }
+
+    #[test]
+    fn per_type_potentials() {
+        let short = || lennard_jones_fn(1.5);
+        let mut mixed = Pairwise::with_potentials(
+            2,
+            vec![
+                short(),
+                lennard_jones_fn(2.5),
+                lennard_jones_fn(2.5),
+                short(),
+            ],
+        );
+        let region = Region::new([20.; 2]);
+        let pos = vec![
+            DVector::from([0., 0.]),
+            DVector::from([2., 0.]),
+            DVector::from([10., 0.]),
+            DVector::from([12., 0.]),
+        ];
+        let mut acc = vec![DVector::default(); 4];
+        PotentialEnergy::<2>::set_types(&mut mixed, &[0, 1, 0, 0]);
+        PotentialEnergy::<2>::compute_forces(&mixed, &pos, &mut acc, &region);
+        let sr6 = (0.5 as Real).powi(6);
+        let u = 4. * sr6 * (sr6 - 1.);
+        assert!((PotentialEnergy::<2>::u_sum(&mixed) - u).abs() < 1e-6);
+        assert_eq!(DVector::default(), acc[3]);
+    }
+}
@@ -0,0 +1,303 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    cutoff,
+    pair::{PairPotential, Pairwise, Parameterized},
+    species::PairParameters,
+};
+use d_vector::Real;
+
+/// Morse bond, `u = D ((1 - e^(-a (r - r_0)))² - 1)`, with well depth `D` at `r_0`.
+#[derive(Debug, Clone, Copy)]
+pub struct MorseLaw {
+    pub depth: Real,
+    pub width: Real,
+    pub r_min: Real,
+    pub r_cut: Real,
+}
+
+impl PairPotential for MorseLaw {
+    fn energy(&self, rr: Real) -> Real {
+        let e = (-self.width * (rr.sqrt() - self.r_min)).exp();
+        self.depth * e * (e - 2.)
+    }
+
+    fn force_over_r(&self, rr: Real) -> Real {
+        let r = rr.sqrt();
+        let e = (-self.width * (r - self.r_min)).exp();
+        2. * self.width * self.depth * e * (e - 1.) / r
+    }
+
+    fn r_cut(&self) -> Real {
+        self.r_cut
+    }
+}
+
+/// Takes `D` from `ε` and `r_0` from `σ`, keeping the width.
+impl Parameterized for MorseLaw {
+    fn with_parameters(&self, pair: &PairParameters) -> Self {
+        Self {
+            depth: pair.epsilon,
+            width: self.width,
+            r_min: pair.sigma,
+            r_cut: pair.r_cut,
+        }
+    }
+}
+
+/// Buckingham exp-6, `u = A e^(-r / ρ) - C / r^6`.
+#[derive(Debug, Clone, Copy)]
+pub struct BuckinghamLaw {
+    pub a: Real,
+    pub rho: Real,
+    pub c: Real,
+    pub r_cut: Real,
+}
+
+impl PairPotential for BuckinghamLaw {
+    fn energy(&self, rr: Real) -> Real {
+        self.a * (-rr.sqrt() / self.rho).exp() - self.c / (rr * rr * rr)
+    }
+
+    fn force_over_r(&self, rr: Real) -> Real {
+        let r = rr.sqrt();
+        self.a * (-r / self.rho).exp() / (self.rho * r) - 6. * self.c / (rr * rr * rr * rr)
+    }
+
+    fn r_cut(&self) -> Real {
+        self.r_cut
+    }
+
+    /// Dispersion tail only; the exponential repulsion is negligible beyond the cutoff.
+    fn tail(&self, dim: usize) -> Option<(Real, Real)> {
+        Some(cutoff::power_tail(-self.c, 6., self.r_cut, dim))
+    }
+}
+
+/// Reads `A`, `ρ` and `C` in units of the pair's `ε` and `σ`:
+/// `A ε`, `ρ σ` and `C ε σ^6`.
+impl Parameterized for BuckinghamLaw {
+    fn with_parameters(&self, pair: &PairParameters) -> Self {
+        Self {
+            a: self.a * pair.epsilon,
+            rho: self.rho * pair.sigma,
+            c: self.c * pair.epsilon * pair.sigma.powi(6),
+            r_cut: pair.r_cut,
+        }
+    }
+}
+
+/// Screened Coulomb, `u = A e^(-κ r) / r`.
+#[derive(Debug, Clone, Copy)]
+pub struct YukawaLaw {
+    pub strength: Real,
+    pub kappa: Real,
+    pub r_cut: Real,
+}
+
+impl PairPotential for YukawaLaw {
+    fn energy(&self, rr: Real) -> Real {
+        let r = rr.sqrt();
+        self.strength * (-self.kappa * r).exp() / r
+    }
+
+    fn force_over_r(&self, rr: Real) -> Real {
+        self.energy(rr) * (self.kappa * rr.sqrt() + 1.) / rr
+    }
+
+    fn r_cut(&self) -> Real {
+        self.r_cut
+    }
+}
+
+/// Reads `A` and `κ` in units of the pair's `ε` and `σ`: `A ε σ` and `κ / σ`.
+impl Parameterized for YukawaLaw {
+    fn with_parameters(&self, pair: &PairParameters) -> Self {
+        Self {
+            strength: self.strength * pair.epsilon * pair.sigma,
+            kappa: self.kappa / pair.sigma,
+            r_cut: pair.r_cut,
+        }
+    }
+}
+
+/// Ultrasoft Gaussian core, `u = ε e^(-(r / σ)²)`.
+#[derive(Debug, Clone, Copy)]
+pub struct GaussianCoreLaw {
+    pub sigma: Real,
+    pub epsilon: Real,
+    pub r_cut: Real,
+}
+
+impl PairPotential for GaussianCoreLaw {
+    fn energy(&self, rr: Real) -> Real {
+        self.epsilon * (-rr / (self.sigma * self.sigma)).exp()
+    }
+
+    fn force_over_r(&self, rr: Real) -> Real {
+        2. * self.energy(rr) / (self.sigma * self.sigma)
+    }
+
+    fn r_cut(&self) -> Real {
+        self.r_cut
+    }
+}
+
+impl Parameterized for GaussianCoreLaw {
+    fn with_parameters(&self, pair: &PairParameters) -> Self {
+        Self {
+            sigma: pair.sigma,
+            epsilon: pair.epsilon,
+            r_cut: pair.r_cut,
+        }
+    }
+}
+
+pub type Morse = Pairwise<MorseLaw>;
+pub type Buckingham = Pairwise<BuckinghamLaw>;
+pub type Yukawa = Pairwise<YukawaLaw>;
+pub type GaussianCore = Pairwise<GaussianCoreLaw>;
+
+impl Morse {
+    pub fn new(depth: Real, width: Real, r_min: Real, r_cut: Real) -> Self {
+        Self::with_potential(MorseLaw {
+            depth,
+            width,
+            r_min,
+            r_cut,
+        })
+    }
+}
+
+impl Buckingham {
+    pub fn new(a: Real, rho: Real, c: Real, r_cut: Real) -> Self {
+        Self::with_potential(BuckinghamLaw { a, rho, c, r_cut })
+    }
+}
+
+impl Yukawa {
+    pub fn new(strength: Real, kappa: Real, r_cut: Real) -> Self {
+        Self::with_potential(YukawaLaw {
+            strength,
+            kappa,
+            r_cut,
+        })
+    }
+
+    /// Charged colloids with valences `z` in a medium of Bjerrum length `l_B`,
+    /// `A_ij = z_i z_j l_B` in units of `kT`.
+    pub fn charged(z: &[Real], bjerrum: Real, kappa: Real, r_cut: Real) -> Self {
+        Self::per_type(z.len(), |i, j| YukawaLaw {
+            strength: z[i] * z[j] * bjerrum,
+            kappa,
+            r_cut,
+        })
+    }
+}
+
+impl GaussianCore {
+    pub fn new(sigma: Real, epsilon: Real, r_cut: Real) -> Self {
+        Self::with_potential(GaussianCoreLaw {
+            sigma,
+            epsilon,
+            r_cut,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{boundaries::Region, potential::PotentialEnergy, species::PairTable, test_support};
+    use d_vector::DVector;
+
+    /// A jittered 2×2×2 cluster with spacing `a`, well inside a large box.
+    fn cluster(a: Real) -> (Region<3>, Vec<DVector<3>>) {
+        let mut pos: Vec<_> = (0..8)
+            .map(|n| a * DVector::from([(n & 1) as Real, (n >> 1 & 1) as Real, (n >> 2) as Real]))
+            .collect();
+        test_support::jitter(&mut pos, 0.2 * a, 16);
+        (Region::new([30.; 3]), pos)
+    }
+
+    /// Compares every force component with a central difference of `u_sum`.
+    fn assert_consistent(potential: &mut dyn PotentialEnergy<3>, types: &[usize], a: Real) {
+        let (region, mut pos) = cluster(a);
+        potential.set_types(types);
+        let mut acc = vec![DVector::default(); pos.len()];
+        potential.compute_forces(&pos, &mut acc, &region);
+        let mut scratch = acc.clone();
+        let h = 1e-3 * a;
+        for j in 0..pos.len() {
+            for k in 0..3 {
+                let mut step = [0.; 3];
+                step[k] = h;
+                let step = DVector::from(step);
+                pos[j] += &step;
+                potential.compute_forces(&pos, &mut scratch, &region);
+                let u_plus = potential.u_sum();
+                pos[j] -= &(2. * &step);
+                potential.compute_forces(&pos, &mut scratch, &region);
+                let u_minus = potential.u_sum();
+                pos[j] += &step;
+                let force = -(u_plus - u_minus) / (2. * h);
+                let f = acc[j].components()[k];
+                assert!(
+                    (f - force).abs() < 2e-2 * (1. + f.abs()),
+                    "molecule {j}, axis {k}: {f} vs {force}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn morse_forces() {
+        let mut morse = Morse::new(1., 3., 1., 3.);
+        assert_consistent(&mut morse, &[], 1.);
+        let law = morse.potential(0, 0);
+        assert!((law.energy(1.) + 1.).abs() < 1e-6);
+        assert!(law.force_over_r(1.).abs() < 1e-6);
+        let mut mixed = Morse::new(1., 3., 1., 3.).pair_table(PairTable::kob_andersen(3.));
+        assert_consistent(&mut mixed, &[0, 1, 0, 0, 1, 0, 1, 0], 1.);
+    }
+
+    #[test]
+    fn buckingham_forces() {
+        let mut buckingham = Buckingham::new(1000., 0.2, 5., 4.);
+        assert_consistent(&mut buckingham, &[], 1.3);
+        let law = buckingham.potential(0, 0);
+        let r: Real = 1.5;
+        let u = 1000. * (-r / 0.2).exp() - 5. / r.powi(6);
+        assert!((law.energy(r * r) - u).abs() < 1e-5);
+        assert!(PotentialEnergy::<3>::tail_correction(&buckingham, 100, 200.).is_some());
+        let mut mixed = Buckingham::new(1000., 0.2, 5., 4.).pair_table(PairTable::kob_andersen(4.));
+        let law = mixed.potential(1, 1);
+        let (sigma, epsilon): (Real, Real) = (0.88, 0.5);
+        let u = 1000. * epsilon * (-r / (0.2 * sigma)).exp() - 5. * epsilon * (sigma / r).powi(6);
+        assert!((law.energy(r * r) - u).abs() < 1e-5);
+        assert_consistent(&mut mixed, &[0, 1, 0, 0, 1, 0, 1, 0], 1.3);
+    }
+
+    #[test]
+    fn yukawa_forces() {
+        let mut yukawa = Yukawa::new(2., 1.5, 6.);
+        assert_consistent(&mut yukawa, &[], 1.);
+        let mut charged = Yukawa::charged(&[1., -2.], 0.7, 1.5, 6.);
+        assert_eq!(-1.4, charged.potential(1, 0).strength);
+        assert_consistent(&mut charged, &[0, 1, 1, 0, 1, 0, 0, 1], 1.);
+        let mut mixed = Yukawa::new(2., 1.5, 6.).pair_table(PairTable::kob_andersen(6.));
+        let law = mixed.potential(0, 1);
+        assert!((law.strength - 2. * 1.5 * 0.8).abs() < 1e-6);
+        assert!((law.kappa - 1.5 / 0.8).abs() < 1e-6);
+        assert_consistent(&mut mixed, &[0, 1, 1, 0, 1, 0, 0, 1], 1.);
+    }
+
+    #[test]
+    fn gaussian_core_forces() {
+        let mut gaussian = GaussianCore::new(1., 1., 4.);
+        assert_consistent(&mut gaussian, &[], 0.5);
+        let law = gaussian.potential(0, 0);
+        assert_eq!(1., law.energy(0.));
+        assert!((law.energy(1.) - (-1 as Real).exp()).abs() < 1e-6);
+    }
+}
@@ -1,22 +1,52 @@
 #![allow(unused, dead_code)]
 
-use crate::boundaries::BoundaryConditions;
+use crate::{boundaries::BoundaryConditions, cutoff::TailCorrection, neighbor_list::NeighborList};
 use d_vector::{DVector, Real};
 use std::{cell::Cell, fmt::Debug};
 
-pub trait PotentialEnergy<const D: usize>: Debug {
+pub type VirialTensor<const D: usize> = [[Real; D]; D];
+
+pub trait PotentialEnergy<const D: usize>: Debug + Send + Sync {
     fn compute_forces(
         &self,
         pos: &[DVector<D>],
         acc: &mut [DVector<D>],
         boundaries: &dyn BoundaryConditions<D>,
     );
+    /// Called by the job before a run with the particle types held in the state.
+    fn set_types(&mut self, types: &[usize]) {}
+    /// Called by the job before a run with the particle charges held in the state.
+    fn set_charges(&mut self, charges: &[Real]) {}
     fn u_sum(&self) -> Real {
         0.0
     }
     fn virial_sum(&self) -> Real {
         0.0
     }
+    /// Sum of `dr_a f_b` over interacting pairs; its trace is `virial_sum`.
+    fn virial_tensor(&self) -> Option<VirialTensor<D>> {
+        None
+    }
+    /// Per-molecule share of the potential energy, half of every pair energy.
+    /// Only potentials that opt in report it.
+    fn particle_energies(&self) -> Option<Vec<Real>> {
+        None
+    }
+    /// Per-molecule virial tensors `½ Σ_j dr_a f_b`, summing to `virial_tensor`.
+    fn particle_virials(&self) -> Option<Vec<VirialTensor<D>>> {
+        None
+    }
+    /// Energy and pressure missing beyond the cutoff for `n_mol` molecules in `volume`.
+    fn tail_correction(&self, n_mol: usize, volume: Real) -> Option<TailCorrection> {
+        None
+    }
+    /// Energy and virial of every term of a sum of potentials.
+    fn term_sums(&self) -> Option<Vec<(Real, Real)>> {
+        None
+    }
+    fn neighbors(&self) -> Option<&NeighborList> {
+        None
+    }
 }
 
 #[derive(Debug, Default)]
@@ -29,5 +59,4 @@ impl<const D: usize> PotentialEnergy<D> for NoInteraction {
         _: &dyn BoundaryConditions<D>,
     ) {
     }
-
 }
@@ -1,18 +1,47 @@
 #![allow(unused, dead_code)]
 
-use crate::potential::PotentialEnergy;
-use d_vector::DVector;
-use std::fmt::Debug;
+use crate::{
+    barostat,
+    boundaries::BoundaryConditions,
+    potential::PotentialEnergy,
+    statistics::{self, SeriesStats},
+    thermostat,
+};
+use d_vector::{DVector, Real};
+use std::{
+    cell::{Cell, RefCell},
+    fmt::{self, Debug, Display},
+};
 
-pub trait Props<const D: usize>: Debug {
+pub trait Props<const D: usize>: Debug + Send {
+    /// Whether the props can be sampled in `boundaries`, checked when the job is built.
+    fn check(&self, boundaries: &dyn BoundaryConditions<D>) -> Result<(), String> {
+        Ok(())
+    }
     fn reset(&self);
-    fn eval_props(&self, u: &dyn PotentialEnergy<D>, pos: &[DVector<D>], vel: &[DVector<D>]);
+    /// `unwrapped` are the positions with the job's periodic image crossings undone,
+    /// `delta_t` is the job's time step and `time` its `Job::time_now`.
+    #[allow(clippy::too_many_arguments)]
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<D>,
+        pos: &[DVector<D>],
+        vel: &[DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        unwrapped: &[DVector<D>],
+        delta_t: Real,
+        time: Real,
+    );
     fn accum_props(&self);
     fn need_avg(&self, step_count: usize) -> bool {
         true
     }
     fn avg_props(&self);
     fn summarize(&self) {}
+    fn summary(&self) -> Option<PropSummary> {
+        None
+    }
 }
 
 #[derive(Debug, Default)]
@@ -21,9 +50,419 @@ pub struct TrivialProps<const D: usize>;
 impl<const D: usize> Props<D> for TrivialProps<D> {
     fn reset(&self) {}
 
-    fn eval_props(&self, u: &dyn PotentialEnergy<D>, pos: &[DVector<D>], vel: &[DVector<D>]) {}
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<D>,
+        pos: &[DVector<D>],
+        vel: &[DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        unwrapped: &[DVector<D>],
+        delta_t: Real,
+        time: Real,
+    ) {
+    }
 
     fn accum_props(&self) {}
 
     fn avg_props(&self) {}
 }
+
+/// A measured value together with its running sums and the accumulated series
+/// since the last reset, kept for the blocking error analysis.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Prop {
+    pub val: Real,
+    sum: Real,
+    sum2: Real,
+    count: usize,
+    series: Vec<Real>,
+}
+
+impl Prop {
+    pub fn accum(&mut self) {
+        self.sum += self.val;
+        self.sum2 += self.val * self.val;
+        self.count += 1;
+        self.series.push(self.val);
+    }
+
+    pub fn reset(&mut self) {
+        self.sum = 0.;
+        self.sum2 = 0.;
+        self.count = 0;
+        self.series.clear();
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    pub fn mean(&self) -> Real {
+        if self.count > 0 {
+            self.sum / self.count as Real
+        } else {
+            0.
+        }
+    }
+
+    pub fn std_dev(&self) -> Real {
+        if self.count > 0 {
+            let mean = self.mean();
+            (self.sum2 / self.count as Real - mean * mean)
+                .max(0.)
+                .sqrt()
+        } else {
+            0.
+        }
+    }
+
+    pub fn series(&self) -> &[Real] {
+        &self.series
+    }
+
+    /// Blocking error, statistical inefficiency and effective sample size of the series.
+    pub fn stats(&self) -> Option<SeriesStats> {
+        statistics::series_stats(&self.series)
+    }
+
+    pub fn entry(&self, name: &str) -> PropEntry {
+        PropEntry {
+            stats: self.stats(),
+            ..PropEntry::new(name, self.mean(), self.std_dev())
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropEntry {
+    pub name: String,
+    pub mean: Real,
+    pub std_dev: Real,
+    pub stats: Option<SeriesStats>,
+}
+
+impl PropEntry {
+    pub fn new(name: &str, mean: Real, std_dev: Real) -> Self {
+        Self {
+            name: name.to_string(),
+            mean,
+            std_dev,
+            stats: None,
+        }
+    }
+}
+
+/// A measured function, one row per abscissa value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropTable {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Real>>,
+}
+
+impl PropTable {
+    pub fn new(name: &str, columns: &[&str]) -> Self {
+        Self {
+            name: name.to_string(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn column(&self, name: &str) -> Option<Vec<Real>> {
+        let index = self.columns.iter().position(|c| c == name)?;
+        Some(self.rows.iter().map(|row| row[index]).collect())
+    }
+}
+
+impl Display for PropTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "# {}", self.name)?;
+        writeln!(f, "# {}", self.columns.join(" "))?;
+        for row in self.rows.iter() {
+            let values: Vec<_> = row.iter().map(|v| format!("{:.5}", v)).collect();
+            writeln!(f, "{}", values.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropSummary {
+    pub step_count: usize,
+    pub entries: Vec<PropEntry>,
+    pub tables: Vec<PropTable>,
+}
+
+impl PropSummary {
+    pub fn get(&self, name: &str) -> Option<&PropEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    pub fn table(&self, name: &str) -> Option<&PropTable> {
+        self.tables.iter().find(|table| table.name == name)
+    }
+}
+
+impl Display for PropSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:6}", self.step_count)?;
+        for entry in self.entries.iter() {
+            write!(f, " {} {:.4} {:.4}", entry.name, entry.mean, entry.std_dev)?;
+            if let Some(stats) = &entry.stats {
+                write!(f, " ±{:.4} s={:.1}", stats.error, stats.inefficiency)?;
+            }
+        }
+        for table in self.tables.iter() {
+            write!(f, "\n{}", table)?;
+        }
+        Ok(())
+    }
+}
+
+/// Kinetic and total energy per molecule, temperature and pressure,
+/// averaged over every `step_avg` steps.
+#[derive(Debug)]
+pub struct ThermoProps<const D: usize> {
+    step_avg: usize,
+    print: bool,
+    tail: bool,
+    step_count: Cell<usize>,
+    kin_energy: RefCell<Prop>,
+    tot_energy: RefCell<Prop>,
+    temperature: RefCell<Prop>,
+    pressure: RefCell<Prop>,
+    last: RefCell<Option<PropSummary>>,
+}
+
+impl<const D: usize> Default for ThermoProps<D> {
+    fn default() -> Self {
+        Self::new(100)
+    }
+}
+
+impl<const D: usize> ThermoProps<D> {
+    pub fn new(step_avg: usize) -> Self {
+        Self {
+            step_avg: step_avg.max(1),
+            print: false,
+            tail: false,
+            step_count: Cell::new(0),
+            kin_energy: RefCell::default(),
+            tot_energy: RefCell::default(),
+            temperature: RefCell::default(),
+            pressure: RefCell::default(),
+            last: RefCell::new(None),
+        }
+    }
+
+    pub fn print(mut self, print: bool) -> Self {
+        self.print = print;
+        self
+    }
+
+    /// Adds the potential's long-range tail correction to the total energy and pressure.
+    pub fn tail_corrections(mut self, tail: bool) -> Self {
+        self.tail = tail;
+        self
+    }
+
+    fn props(&self) -> [(&'static str, &RefCell<Prop>); 4] {
+        [
+            ("kin_energy", &self.kin_energy),
+            ("tot_energy", &self.tot_energy),
+            ("temperature", &self.temperature),
+            ("pressure", &self.pressure),
+        ]
+    }
+}
+
+impl<const D: usize> Props<D> for ThermoProps<D> {
+    fn reset(&self) {
+        for (_, prop) in self.props() {
+            prop.borrow_mut().reset();
+        }
+    }
+
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<D>,
+        pos: &[DVector<D>],
+        vel: &[DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        unwrapped: &[DVector<D>],
+        delta_t: Real,
+        time: Real,
+    ) {
+        let n_mol = vel.len().max(1) as Real;
+        let kinetic = thermostat::kinetic_energy(vel, masses);
+        let volume = barostat::volume(boundaries);
+        let tail = volume
+            .filter(|_| self.tail)
+            .and_then(|volume| u.tail_correction(vel.len(), volume))
+            .unwrap_or_default();
+        let values = [
+            kinetic / n_mol,
+            (kinetic + u.u_sum() + tail.energy) / n_mol,
+            thermostat::temperature(vel, masses),
+            volume
+                .map(|volume| barostat::pressure(vel, masses, u.virial_sum(), volume))
+                .map_or(0., |p| p + tail.pressure),
+        ];
+        for ((_, prop), val) in self.props().iter().zip(values) {
+            prop.borrow_mut().val = val;
+        }
+    }
+
+    fn accum_props(&self) {
+        for (_, prop) in self.props() {
+            prop.borrow_mut().accum();
+        }
+    }
+
+    fn need_avg(&self, step_count: usize) -> bool {
+        self.step_count.set(step_count);
+        step_count.is_multiple_of(self.step_avg)
+    }
+
+    fn avg_props(&self) {
+        let entries = self
+            .props()
+            .iter()
+            .map(|(name, prop)| prop.borrow().entry(name))
+            .collect();
+        *self.last.borrow_mut() = Some(PropSummary {
+            step_count: self.step_count.get(),
+            entries,
+            tables: Vec::new(),
+        });
+    }
+
+    fn summarize(&self) {
+        if self.print {
+            if let Some(summary) = self.last.borrow().as_ref() {
+                println!("{}", summary);
+            }
+        }
+    }
+
+    fn summary(&self) -> Option<PropSummary> {
+        self.last.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cutoff::Cutoff,
+        initial_state,
+        job::{Job, JobSetup},
+        lennard_jones::LennardJones,
+        rdf,
+    };
+
+    #[test]
+    fn prop_statistics() {
+        let mut p = Prop::default();
+        for val in [1., 2., 3., 4.] {
+            p.val = val;
+            p.accum();
+        }
+        assert_eq!(4, p.count());
+        assert_eq!(2.5, p.mean());
+        assert!((p.std_dev() - 1.25_f32.sqrt()).abs() < 1e-6);
+        assert_eq!(&[1., 2., 3., 4.], p.series());
+        assert_eq!(2.5, p.stats().unwrap().mean);
+        p.reset();
+        assert_eq!(0., p.mean());
+    }
+
+    #[test]
+    fn thermo_props_summary() {
+        let (boundaries, pos) = initial_state::cubic_lattice(216, 0.8);
+        let mut job: Job<3> = JobSetup::build()
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .random_vel(1.)
+            .potential(LennardJones::new((2 as Real).powf(1. / 6.)))
+            .props(ThermoProps::new(50))
+            .job();
+        job.run(120);
+        let summary = job.props().summary().unwrap();
+        assert_eq!(100, summary.step_count);
+        let temperature = summary.get("temperature").unwrap();
+        assert!(temperature.mean > 0. && temperature.std_dev > 0.);
+        let stats = temperature.stats.unwrap();
+        assert_eq!(50, stats.n);
+        assert!(stats.inefficiency >= 1. && stats.effective_samples <= 50.);
+        assert!(summary.to_string().contains("s="));
+        let tot_energy = summary.get("tot_energy").unwrap();
+        assert!(tot_energy.std_dev < 1e-2 * tot_energy.mean.abs());
+        assert!(summary.get("pressure").unwrap().mean > 0.);
+    }
+
+    #[test]
+    fn tail_corrected_thermo_props() {
+        let lj = || LennardJones::new(2.5).cutoff(Cutoff::Truncated);
+        let run = |props: ThermoProps<3>| {
+            let (boundaries, pos) = initial_state::cubic_lattice(216, 0.8);
+            let mut job: Job<3> = JobSetup::build()
+                .boundaries(boundaries)
+                .init_pos(pos)
+                .potential(lj())
+                .props(props)
+                .job();
+            job.run(1);
+            job.props().summary().unwrap()
+        };
+        let plain = run(ThermoProps::new(1));
+        let corrected = run(ThermoProps::new(1).tail_corrections(true));
+        let tail = PotentialEnergy::<3>::tail_correction(&lj(), 216, 270.).unwrap();
+        let de = corrected.get("tot_energy").unwrap().mean - plain.get("tot_energy").unwrap().mean;
+        let dp = corrected.get("pressure").unwrap().mean - plain.get("pressure").unwrap().mean;
+        assert!((de - tail.energy / 216.).abs() < 1e-4);
+        assert!((dp - tail.pressure).abs() < 1e-4);
+        assert!(de < 0. && dp < 0.);
+    }
+
+    #[test]
+    fn tail_for_offset_cutoffs() {
+        let tail = |cutoff| {
+            let lj = LennardJones::new(2.5).cutoff(cutoff);
+            PotentialEnergy::<3>::tail_correction(&lj, 216, 270.)
+        };
+        let truncated = tail(Cutoff::Truncated).unwrap();
+        let ball = rdf::unit_ball_volume(3) * (2.5 as Real).powi(3);
+        let pairs = 0.5 * 216. * 0.8 * ball;
+        for (cutoff, shift) in [
+            (Cutoff::Offset(1.), -1.),
+            (
+                Cutoff::Shifted,
+                4. * (2.5 as Real).powi(-6) * ((2.5 as Real).powi(-6) - 1.),
+            ),
+        ] {
+            let tail = tail(cutoff).unwrap();
+            assert_eq!(truncated.pressure, tail.pressure);
+            assert!((tail.energy - truncated.energy - pairs * shift).abs() < 1e-3);
+        }
+        assert_eq!(None, tail(Cutoff::ShiftedForce));
+        assert_eq!(None, tail(Cutoff::Switched { from: 0.8 }));
+
+        let (boundaries, pos) = initial_state::cubic_lattice(216, 0.8);
+        let mut job: Job<3> = JobSetup::build()
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .potential(LennardJones::default())
+            .props(ThermoProps::new(1).tail_corrections(true))
+            .job();
+        job.run(1);
+        let summary = job.props().summary().unwrap();
+        let tot_energy = (job.kinetic_energy() + job.potential_energy()) / 216.;
+        let corrected = summary.get("tot_energy").unwrap().mean;
+        let expected = tot_energy + tail(Cutoff::Offset(1.)).unwrap().energy / 216.;
+        assert!((corrected - expected).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,310 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    barostat,
+    boundaries::BoundaryConditions,
+    cells::{self, PairSearch},
+    potential::PotentialEnergy,
+    prop::{PropSummary, PropTable, Props},
+};
+use d_vector::{DVector, Real};
+use std::{
+    cell::{Cell, RefCell},
+    f64::consts::PI,
+};
+
+/// Volume of the unit ball in `D` dimensions.
+pub fn unit_ball_volume(dim: usize) -> Real {
+    match dim {
+        0 => 1.,
+        1 => 2.,
+        _ => (2. * PI / dim as f64) as Real * unit_ball_volume(dim - 2),
+    }
+}
+
+/// Half the shortest box length, unbounded without a periodic box.
+fn half_box<const D: usize>(boundaries: &dyn BoundaryConditions<D>) -> Real {
+    boundaries.box_size().map_or(Real::MAX, |size| {
+        size.iter().fold(Real::MAX, |half, l| half.min(l / 2.))
+    })
+}
+
+/// Radial distribution function g(r), histogrammed every `every` steps from
+/// minimum-image pair distances up to `range` and averaged over `step_avg` steps.
+/// The table also carries the running coordination number n(r). In a periodic box
+/// `range` may not exceed half the shortest box length, see `check`; if the box
+/// shrinks below that, bins beyond its half are left out of the sample.
+#[derive(Debug)]
+pub struct RadialDistribution<const D: usize> {
+    range: Real,
+    bin_width: Real,
+    volume: Option<Real>,
+    every: usize,
+    step_avg: usize,
+    steps: Cell<usize>,
+    step_count: Cell<usize>,
+    samples: Cell<usize>,
+    hist: RefCell<Vec<Bin>>,
+    last: RefCell<Option<PropSummary>>,
+}
+
+/// Pairs counted in one shell, with the ideal-gas pair density and the
+/// molecules of the samples that covered it.
+#[derive(Debug, Clone, Copy, Default)]
+struct Bin {
+    pairs: Real,
+    ideal: Real,
+    molecules: Real,
+}
+
+impl<const D: usize> RadialDistribution<D> {
+    pub fn new(range: Real, bin_width: Real) -> Self {
+        let n_bins = (range / bin_width).ceil().max(1.) as usize;
+        Self {
+            range,
+            bin_width,
+            volume: None,
+            every: 1,
+            step_avg: 1000,
+            steps: Cell::new(0),
+            step_count: Cell::new(0),
+            samples: Cell::new(0),
+            hist: RefCell::new(vec![Bin::default(); n_bins]),
+            last: RefCell::new(None),
+        }
+    }
+
+    /// Volume of the ideal gas that normalizes g(r) for boundaries without a box.
+    pub fn volume(mut self, volume: Real) -> Self {
+        self.volume = Some(volume);
+        self
+    }
+
+    pub fn every(mut self, steps: usize) -> Self {
+        self.every = steps.max(1);
+        self
+    }
+
+    pub fn step_avg(mut self, steps: usize) -> Self {
+        self.step_avg = steps.max(1);
+        self
+    }
+
+    /// Outer edge of bin `i`.
+    fn r_out(&self, i: usize) -> Real {
+        ((i + 1) as Real * self.bin_width).min(self.range)
+    }
+
+    /// Adds the pair distances of one configuration to the histogram, in the bins
+    /// that lie within half the box. Skipped without a volume to normalize by.
+    pub fn sample(&self, pos: &[DVector<D>], boundaries: &dyn BoundaryConditions<D>) {
+        let Some(volume) = barostat::volume(boundaries).or(self.volume) else {
+            return;
+        };
+        let limit = self.range.min(half_box(boundaries));
+        let mut hist = self.hist.borrow_mut();
+        let n_bins = (0..hist.len())
+            .take_while(|i| self.r_out(*i) <= limit)
+            .count();
+        let rr_limit = limit * limit;
+        cells::for_each_pair(pos, boundaries, limit, PairSearch::Cells, |j1, j2| {
+            let mut dr = &pos[j1] - &pos[j2];
+            boundaries.wrap(&mut dr);
+            let rr = dr.square_length();
+            if rr < rr_limit {
+                let bin = (rr.sqrt() / self.bin_width) as usize;
+                if bin < n_bins {
+                    hist[bin].pairs += 1.;
+                }
+            }
+        });
+        let n_mol = pos.len();
+        let ideal = 0.5 * (n_mol * n_mol.saturating_sub(1)) as Real / volume;
+        for bin in hist[..n_bins].iter_mut() {
+            bin.ideal += ideal;
+            bin.molecules += n_mol as Real;
+        }
+        self.samples.set(self.samples.get() + 1);
+    }
+
+    /// Columns `r` (bin centre), `g` and the coordination number `n`
+    /// counted up to the outer edge of the bin.
+    pub fn table(&self) -> PropTable {
+        let mut table = PropTable::new("rdf", &["r", "g", "n"]);
+        let samples = self.samples.get();
+        if samples == 0 {
+            return table;
+        }
+        let ball = unit_ball_volume(D);
+        let mut coordination = 0.;
+        for (i, bin) in self.hist.borrow().iter().enumerate() {
+            if bin.molecules == 0. {
+                break;
+            }
+            let r_in = i as Real * self.bin_width;
+            let r_out = self.r_out(i);
+            let shell = ball * (r_out.powi(D as i32) - r_in.powi(D as i32));
+            coordination += 2. * bin.pairs / bin.molecules;
+            table.rows.push(vec![
+                0.5 * (r_in + r_out),
+                bin.pairs / (bin.ideal * shell),
+                coordination,
+            ]);
+        }
+        table
+    }
+}
+
+impl<const D: usize> Props<D> for RadialDistribution<D> {
+    /// Whether `boundaries` allow the whole range: it must fit in half the box,
+    /// and without a box `volume` must be given.
+    fn check(&self, boundaries: &dyn BoundaryConditions<D>) -> Result<(), String> {
+        if barostat::volume(boundaries).or(self.volume).is_none() {
+            return Err("g(r) without a periodic box needs a volume".to_string());
+        }
+        let half_box = half_box(boundaries);
+        if self.range > half_box {
+            return Err(format!(
+                "g(r) range {} exceeds half the box, {half_box}",
+                self.range
+            ));
+        }
+        Ok(())
+    }
+
+    fn reset(&self) {
+        self.hist
+            .borrow_mut()
+            .iter_mut()
+            .for_each(|bin| *bin = Bin::default());
+        self.samples.set(0);
+    }
+
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<D>,
+        pos: &[DVector<D>],
+        vel: &[DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        unwrapped: &[DVector<D>],
+        delta_t: Real,
+        time: Real,
+    ) {
+        self.steps.set(self.steps.get() + 1);
+        if self.steps.get().is_multiple_of(self.every) {
+            self.sample(pos, boundaries);
+        }
+    }
+
+    fn accum_props(&self) {}
+
+    fn need_avg(&self, step_count: usize) -> bool {
+        self.step_count.set(step_count);
+        step_count.is_multiple_of(self.step_avg)
+    }
+
+    fn avg_props(&self) {
+        *self.last.borrow_mut() = Some(PropSummary {
+            step_count: self.step_count.get(),
+            entries: Vec::new(),
+            tables: vec![self.table()],
+        });
+    }
+
+    fn summary(&self) -> Option<PropSummary> {
+        self.last.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        boundaries::Region,
+        initial_state,
+        job::{Job, JobSetup},
+        test_support,
+    };
+
+    #[derive(Debug)]
+    struct Unbounded;
+
+    impl<const D: usize> BoundaryConditions<D> for Unbounded {
+        fn wrap(&self, pos: &mut DVector<D>) {}
+    }
+
+    #[test]
+    fn ball_volumes() {
+        assert!((unit_ball_volume(2) - std::f32::consts::PI).abs() < 1e-6);
+        assert!((unit_ball_volume(3) - 4. / 3. * std::f32::consts::PI).abs() < 1e-6);
+    }
+
+    #[test]
+    fn square_lattice_coordination() {
+        let (region, pos) = initial_state::cubic_lattice::<2>(400, 1.);
+        let rdf = RadialDistribution::new(1.2, 0.1);
+        rdf.sample(&pos, &region);
+        let n = rdf.table().column("n").unwrap();
+        assert_eq!(0., n[8]);
+        assert!((n[11] - 4.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn ideal_gas_is_uncorrelated() {
+        let size = 10.;
+        let region = Region::new([size; 3]);
+        let rdf = RadialDistribution::new(3., 0.25);
+        for seed in 0..20 {
+            let pos: Vec<DVector<3>> = test_support::random_pos(1000, size, seed);
+            rdf.sample(&pos, &region);
+        }
+        for g in rdf.table().column("g").unwrap().iter().skip(2) {
+            assert!((g - 1.).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn last_bin_of_uneven_range() {
+        let size = 10.;
+        let region = Region::new([size; 3]);
+        let rdf = RadialDistribution::new(3.1, 0.25);
+        for seed in 0..20 {
+            let pos: Vec<DVector<3>> = test_support::random_pos(1000, size, seed);
+            rdf.sample(&pos, &region);
+        }
+        let table = rdf.table();
+        assert_eq!(13, table.rows.len());
+        assert!((table.column("r").unwrap()[12] - 3.05).abs() < 1e-5);
+        assert!((table.column("g").unwrap()[12] - 1.).abs() < 0.1);
+    }
+
+    #[test]
+    fn range_beyond_half_box() {
+        let (region, pos) = initial_state::cubic_lattice::<2>(100, 1.);
+        let rdf = RadialDistribution::new(6., 0.5);
+        assert!(rdf
+            .check(&region)
+            .unwrap_err()
+            .contains("exceeds half the box"));
+        assert!(RadialDistribution::new(5., 0.5).check(&region).is_ok());
+        let unbounded = RadialDistribution::<2>::new(5., 0.5);
+        assert!(unbounded.check(&Unbounded).is_err());
+        assert!(unbounded.volume(100.).check(&Unbounded).is_ok());
+
+        // Only the bins within half the box are sampled.
+        rdf.sample(&pos, &region);
+        assert_eq!(10, rdf.table().rows.len());
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds half the box")]
+    fn job_rejects_range_beyond_half_box() {
+        let (region, pos) = initial_state::cubic_lattice::<2>(100, 1.);
+        let job: Job<2> = JobSetup::build()
+            .boundaries(region)
+            .init_pos(pos)
+            .props(RadialDistribution::new(6., 0.5))
+            .job();
+    }
+}
@@ -0,0 +1,407 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    boundaries::BoundaryConditions,
+    potential::PotentialEnergy,
+    prop::{PropSummary, PropTable, Props},
+    rdf::unit_ball_volume,
+};
+use d_vector::{DVector, Real};
+use std::{
+    cell::{Cell, RefCell},
+    collections::VecDeque,
+    f32::consts::TAU,
+};
+
+/// Lags of the multiple-tau scheme: 0 and then `k · block^l` for `0 < k < block`
+/// and every level `l`, up to `max_lag`.
+pub fn block_lags(max_lag: usize, block: usize) -> Vec<usize> {
+    let block = block.max(2);
+    let mut lags = vec![0];
+    let mut spacing = 1;
+    while spacing <= max_lag {
+        lags.extend(
+            (1..block)
+                .map(|k| k * spacing)
+                .filter(|lag| *lag <= max_lag),
+        );
+        spacing *= block;
+    }
+    lags
+}
+
+/// Unwrapped positions and density modes of one sample.
+#[derive(Debug, Clone)]
+struct Frame<const D: usize> {
+    pos: Vec<DVector<D>>,
+    rho: Vec<(Real, Real)>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct LagSums {
+    count: usize,
+    r2: Real,
+    r4: Real,
+    self_part: Real,
+    collective: Real,
+    van_hove: Vec<Real>,
+}
+
+/// Self and collective intermediate scattering functions at wavenumber `k`,
+/// the self part of the van Hove function, the mean-square displacement and
+/// the non-Gaussian parameter `α₂ = D <Δr⁴> / ((D + 2) <Δr²>²) - 1`.
+/// Time origins follow a multiple-tau scheme: level `l` keeps the last `block - 1`
+/// samples taken `block^l` samples apart, and every new one is correlated with them,
+/// so the memory grows with the logarithm of `max_lag` rather than with `max_lag`.
+/// The wavevectors are the box reciprocal vectors closest to `k` along each axis.
+#[derive(Debug)]
+pub struct IntermediateScattering<const D: usize> {
+    k: Real,
+    lags: Vec<usize>,
+    max_lag: usize,
+    block: usize,
+    range: Real,
+    bin_width: Real,
+    every: usize,
+    delta_t: Cell<Real>,
+    step_avg: usize,
+    steps: Cell<usize>,
+    step_count: Cell<usize>,
+    samples: Cell<usize>,
+    n_mol: Cell<usize>,
+    levels: RefCell<Vec<VecDeque<Frame<D>>>>,
+    sums: RefCell<Vec<LagSums>>,
+    last: RefCell<Option<PropSummary>>,
+}
+
+impl<const D: usize> IntermediateScattering<D> {
+    pub fn new(k: Real, max_lag: usize) -> Self {
+        let lags = block_lags(max_lag, 10);
+        Self {
+            k,
+            sums: RefCell::new(vec![LagSums::default(); lags.len()]),
+            lags,
+            max_lag,
+            block: 10,
+            range: 0.,
+            bin_width: 0.,
+            every: 1,
+            delta_t: Cell::new(0.),
+            step_avg: 1000,
+            steps: Cell::new(0),
+            step_count: Cell::new(0),
+            samples: Cell::new(0),
+            n_mol: Cell::new(0),
+            levels: RefCell::new(Vec::new()),
+            last: RefCell::new(None),
+        }
+    }
+
+    /// Lags per level of the multiple-tau scheme, 10 by default.
+    pub fn block(mut self, block: usize) -> Self {
+        self.block = block.max(2);
+        self.lags = block_lags(self.max_lag, self.block);
+        self.sums = RefCell::new(vec![LagSums::default(); self.lags.len()]);
+        self
+    }
+
+    /// Histograms the displacements up to `range` for `G_s(r, t)`.
+    pub fn van_hove(mut self, range: Real, bin_width: Real) -> Self {
+        self.range = range;
+        self.bin_width = bin_width;
+        self
+    }
+
+    pub fn every(mut self, steps: usize) -> Self {
+        self.every = steps.max(1);
+        self
+    }
+
+    pub fn step_avg(mut self, steps: usize) -> Self {
+        self.step_avg = steps.max(1);
+        self
+    }
+
+    fn n_bins(&self) -> usize {
+        if self.bin_width > 0. {
+            (self.range / self.bin_width).ceil() as usize
+        } else {
+            0
+        }
+    }
+
+    fn wave_vectors(&self, size: &[Real; D]) -> Vec<DVector<D>> {
+        (0..D)
+            .map(|a| {
+                let mut k = [0 as Real; D];
+                let n = (self.k * size[a] / TAU).round().max(1.);
+                k[a] = TAU * n / size[a];
+                DVector::from(k)
+            })
+            .collect()
+    }
+
+    fn density_modes(pos: &[DVector<D>], wave_vectors: &[DVector<D>]) -> Vec<(Real, Real)> {
+        wave_vectors
+            .iter()
+            .map(|k| {
+                pos.iter().fold((0., 0.), |(re, im), r| {
+                    let phase: Real = k
+                        .components()
+                        .iter()
+                        .zip(r.components())
+                        .map(|(k, r)| k * r)
+                        .sum();
+                    (re + phase.cos(), im + phase.sin())
+                })
+            })
+            .collect()
+    }
+
+    /// Adds one configuration of unwrapped positions in a box of the given size.
+    pub fn sample(&self, unwrapped: &[DVector<D>], size: &[Real; D]) {
+        let index = self.samples.get();
+        self.samples.set(index + 1);
+        self.n_mol.set(unwrapped.len());
+        let wave_vectors = self.wave_vectors(size);
+        let frame = Frame {
+            pos: unwrapped.to_vec(),
+            rho: Self::density_modes(unwrapped, &wave_vectors),
+        };
+        self.correlate(0, &frame, &frame, &wave_vectors);
+        let mut levels = self.levels.borrow_mut();
+        let mut spacing = 1;
+        for level in 0.. {
+            if spacing > self.max_lag || !index.is_multiple_of(spacing) {
+                break;
+            }
+            if levels.len() == level {
+                levels.push(VecDeque::with_capacity(self.block));
+            }
+            let frames = &mut levels[level];
+            for (k, origin) in frames.iter().enumerate() {
+                self.correlate((k + 1) * spacing, origin, &frame, &wave_vectors);
+            }
+            frames.push_front(frame.clone());
+            frames.truncate(self.block - 1);
+            spacing *= self.block;
+        }
+    }
+
+    /// Adds the displacements and density correlation from `origin` to `frame`
+    /// to the sums of `lag`.
+    fn correlate(
+        &self,
+        lag: usize,
+        origin: &Frame<D>,
+        frame: &Frame<D>,
+        wave_vectors: &[DVector<D>],
+    ) {
+        let Ok(l) = self.lags.binary_search(&lag) else {
+            return;
+        };
+        let n_mol = frame.pos.len().max(1) as Real;
+        let n_bins = self.n_bins();
+        let mut sums = self.sums.borrow_mut();
+        let lag = &mut sums[l];
+        lag.van_hove.resize(n_bins, 0.);
+        let (mut r2, mut r4, mut self_part) = (0., 0., 0.);
+        for (r, r0) in frame.pos.iter().zip(origin.pos.iter()) {
+            let dr = r - r0;
+            let rr = dr.square_length();
+            r2 += rr;
+            r4 += rr * rr;
+            for k in wave_vectors.iter() {
+                let phase: Real = k
+                    .components()
+                    .iter()
+                    .zip(dr.components())
+                    .map(|(k, r)| k * r)
+                    .sum();
+                self_part += phase.cos();
+            }
+            if n_bins > 0 {
+                if let Some(h) = lag.van_hove.get_mut((rr.sqrt() / self.bin_width) as usize) {
+                    *h += 1.;
+                }
+            }
+        }
+        let collective: Real = frame
+            .rho
+            .iter()
+            .zip(origin.rho.iter())
+            .map(|(a, b)| a.0 * b.0 + a.1 * b.1)
+            .sum();
+        lag.count += 1;
+        lag.r2 += r2 / n_mol;
+        lag.r4 += r4 / n_mol;
+        lag.self_part += self_part / (n_mol * D as Real);
+        lag.collective += collective / (n_mol * D as Real);
+    }
+
+    /// Columns `t`, `msd`, `fs` (self), `f` (collective) and `alpha2`,
+    /// one row per lag reached by at least one origin.
+    pub fn table(&self) -> PropTable {
+        let mut table = PropTable::new("isf", &["t", "msd", "fs", "f", "alpha2"]);
+        let lag_time = self.every as Real * self.delta_t.get();
+        for (lag, sums) in self.lags.iter().zip(self.sums.borrow().iter()) {
+            if sums.count == 0 {
+                continue;
+            }
+            let count = sums.count as Real;
+            let (r2, r4) = (sums.r2 / count, sums.r4 / count);
+            let alpha2 = if r2 > 0. {
+                D as Real * r4 / ((D + 2) as Real * r2 * r2) - 1.
+            } else {
+                0.
+            };
+            table.rows.push(vec![
+                *lag as Real * lag_time,
+                r2,
+                sums.self_part / count,
+                sums.collective / count,
+                alpha2,
+            ]);
+        }
+        table
+    }
+
+    /// Columns `t`, `r` and `g_s`, normalized so that `∫ G_s(r, t) d^D r = 1`.
+    pub fn van_hove_table(&self) -> PropTable {
+        let mut table = PropTable::new("van_hove", &["t", "r", "g_s"]);
+        let lag_time = self.every as Real * self.delta_t.get();
+        let ball = unit_ball_volume(D);
+        for (lag, sums) in self.lags.iter().zip(self.sums.borrow().iter()) {
+            let total = (sums.count * self.n_mol.get()) as Real;
+            if sums.count == 0 || total == 0. {
+                continue;
+            }
+            for (i, h) in sums.van_hove.iter().enumerate() {
+                let r_in = i as Real * self.bin_width;
+                let r_out = r_in + self.bin_width;
+                let shell = ball * (r_out.powi(D as i32) - r_in.powi(D as i32));
+                table.rows.push(vec![
+                    *lag as Real * lag_time,
+                    r_in + 0.5 * self.bin_width,
+                    h / (total * shell),
+                ]);
+            }
+        }
+        table
+    }
+
+    fn tables(&self) -> Vec<PropTable> {
+        let mut tables = vec![self.table()];
+        if self.n_bins() > 0 {
+            tables.push(self.van_hove_table());
+        }
+        tables
+    }
+}
+
+impl<const D: usize> Props<D> for IntermediateScattering<D> {
+    fn reset(&self) {
+        for sums in self.sums.borrow_mut().iter_mut() {
+            *sums = LagSums::default();
+        }
+    }
+
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<D>,
+        pos: &[DVector<D>],
+        vel: &[DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        unwrapped: &[DVector<D>],
+        delta_t: Real,
+        time: Real,
+    ) {
+        self.delta_t.set(delta_t);
+        self.steps.set(self.steps.get() + 1);
+        if !self.steps.get().is_multiple_of(self.every) {
+            return;
+        }
+        if let Some(size) = boundaries.box_size() {
+            self.sample(unwrapped, size);
+        }
+    }
+
+    fn accum_props(&self) {}
+
+    fn need_avg(&self, step_count: usize) -> bool {
+        self.step_count.set(step_count);
+        step_count.is_multiple_of(self.step_avg)
+    }
+
+    fn avg_props(&self) {
+        *self.last.borrow_mut() = Some(PropSummary {
+            step_count: self.step_count.get(),
+            entries: Vec::new(),
+            tables: self.tables(),
+        });
+    }
+
+    fn summary(&self) -> Option<PropSummary> {
+        self.last.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        boundaries::Region,
+        job::{Job, JobSetup},
+        langevin::Brownian,
+        potential::NoInteraction,
+        test_support,
+    };
+
+    #[test]
+    fn multiple_tau_lags() {
+        assert_eq!(
+            vec![0, 1, 2, 3, 4, 8, 12, 16, 32, 48, 64],
+            block_lags(100, 4)
+        );
+    }
+
+    #[test]
+    fn brownian_scattering_is_gaussian() {
+        let pos = test_support::random_pos(500, 10., 12);
+        let mut job: Job<3> = JobSetup::build()
+            .delta_t(0.01)
+            .boundaries(Region::new([10.; 3]))
+            .init_pos(pos)
+            .potential(NoInteraction)
+            .integrator(Brownian::new(1., 2.).seed(11))
+            .props(
+                IntermediateScattering::new(2.5, 100)
+                    .van_hove(5., 0.1)
+                    .step_avg(1500),
+            )
+            .job();
+        job.run(1500);
+        let summary = job.props().summary().unwrap();
+        let isf = summary.table("isf").unwrap();
+        // k = 2π · 4 / 10 on every axis, D_s = T / γ = 0.5
+        let k = TAU * 0.4;
+        for row in isf.rows.iter() {
+            let (t, fs, alpha2) = (row[0], row[2], row[4]);
+            assert!((fs - (-k * k * 0.5 * t).exp()).abs() < 0.03);
+            assert!(alpha2.abs() < 0.1);
+        }
+        let van_hove = summary.table("van_hove").unwrap();
+        let last_t = van_hove.rows.last().unwrap()[0];
+        let norm: Real = van_hove
+            .rows
+            .iter()
+            .filter(|row| row[0] == last_t)
+            .map(|row| {
+                let (r_in, r_out) = (row[1] - 0.05, row[1] + 0.05);
+                row[2] * unit_ball_volume(3) * (r_out.powi(3) - r_in.powi(3))
+            })
+            .sum();
+        assert!((norm - 1.).abs() < 0.01);
+    }
+}
@@ -0,0 +1,255 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    cutoff,
+    pair::{PairPotential, Pairwise, Parameterized},
+    species::{PairParameters, PairTable},
+};
+use d_vector::Real;
+
+/// Weeks–Chandler–Andersen: Lennard-Jones cut at its minimum `2^(1/6) σ`
+/// and shifted up by `ε`, so that energy and force vanish at the cutoff.
+#[derive(Debug, Clone, Copy)]
+pub struct WcaLaw {
+    pub sigma: Real,
+    pub epsilon: Real,
+}
+
+impl PairPotential for WcaLaw {
+    fn energy(&self, rr: Real) -> Real {
+        let sr2 = self.sigma * self.sigma / rr;
+        let sr6 = sr2 * sr2 * sr2;
+        self.epsilon * (4. * sr6 * (sr6 - 1.) + 1.)
+    }
+
+    fn force_over_r(&self, rr: Real) -> Real {
+        let sr2 = self.sigma * self.sigma / rr;
+        let sr6 = sr2 * sr2 * sr2;
+        48. * self.epsilon * sr6 * (sr6 - 0.5) / rr
+    }
+
+    fn r_cut(&self) -> Real {
+        (2 as Real).powf(1. / 6.) * self.sigma
+    }
+}
+
+impl Parameterized for WcaLaw {
+    fn with_parameters(&self, pair: &PairParameters) -> Self {
+        Self {
+            sigma: pair.sigma,
+            epsilon: pair.epsilon,
+        }
+    }
+}
+
+/// `u = ε (σ / r)^n`, truncated at `r_cut`.
+#[derive(Debug, Clone, Copy)]
+pub struct InversePowerLaw {
+    pub n: Real,
+    pub sigma: Real,
+    pub epsilon: Real,
+    pub r_cut: Real,
+}
+
+impl PairPotential for InversePowerLaw {
+    fn energy(&self, rr: Real) -> Real {
+        self.epsilon * (self.sigma * self.sigma / rr).powf(0.5 * self.n)
+    }
+
+    fn force_over_r(&self, rr: Real) -> Real {
+        self.n * self.energy(rr) / rr
+    }
+
+    fn r_cut(&self) -> Real {
+        self.r_cut
+    }
+
+    fn tail(&self, dim: usize) -> Option<(Real, Real)> {
+        let c = self.epsilon * self.sigma.powf(self.n);
+        Some(cutoff::power_tail(c, self.n, self.r_cut, dim))
+    }
+}
+
+impl Parameterized for InversePowerLaw {
+    fn with_parameters(&self, pair: &PairParameters) -> Self {
+        Self {
+            n: self.n,
+            sigma: pair.sigma,
+            epsilon: pair.epsilon,
+            r_cut: pair.r_cut,
+        }
+    }
+}
+
+/// Harmonic soft disks, `u = ε / 2 (1 - r / σ)²` for overlapping pairs `r < σ`.
+#[derive(Debug, Clone, Copy)]
+pub struct HarmonicLaw {
+    pub sigma: Real,
+    pub epsilon: Real,
+}
+
+impl PairPotential for HarmonicLaw {
+    fn energy(&self, rr: Real) -> Real {
+        let overlap = 1. - rr.sqrt() / self.sigma;
+        0.5 * self.epsilon * overlap * overlap
+    }
+
+    fn force_over_r(&self, rr: Real) -> Real {
+        let r = rr.sqrt();
+        self.epsilon * (1. - r / self.sigma) / (self.sigma * r)
+    }
+
+    fn r_cut(&self) -> Real {
+        self.sigma
+    }
+}
+
+impl Parameterized for HarmonicLaw {
+    fn with_parameters(&self, pair: &PairParameters) -> Self {
+        Self {
+            sigma: pair.sigma,
+            epsilon: pair.epsilon,
+        }
+    }
+}
+
+pub type Wca = Pairwise<WcaLaw>;
+pub type InversePower = Pairwise<InversePowerLaw>;
+pub type Harmonic = Pairwise<HarmonicLaw>;
+
+impl Wca {
+    pub fn new() -> Self {
+        Self::with_potential(WcaLaw {
+            sigma: 1.,
+            epsilon: 1.,
+        })
+    }
+}
+
+impl Default for Wca {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InversePower {
+    /// Exponent `n`, cut at `r_cut` for unit `σ`.
+    pub fn new(n: Real, r_cut: Real) -> Self {
+        Self::with_potential(InversePowerLaw {
+            n,
+            sigma: 1.,
+            epsilon: 1.,
+            r_cut,
+        })
+    }
+}
+
+impl Harmonic {
+    pub fn new() -> Self {
+        Self::with_potential(HarmonicLaw {
+            sigma: 1.,
+            epsilon: 1.,
+        })
+    }
+}
+
+impl Default for Harmonic {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        boundaries::Region, initial_state, lennard_jones::LennardJones, potential::PotentialEnergy,
+        species::Species, test_support,
+    };
+    use d_vector::DVector;
+
+    /// Energy, force on the second molecule along `x` and virial of a single pair at distance `r`.
+    fn pair_values(potential: &dyn PotentialEnergy<2>, r: Real) -> (Real, Real, Real) {
+        let region = Region::new([20.; 2]);
+        let pos = vec![DVector::from([0., 0.]), DVector::from([r, 0.])];
+        let mut acc = vec![DVector::default(); 2];
+        potential.compute_forces(&pos, &mut acc, &region);
+        (
+            potential.u_sum(),
+            acc[1].components()[0],
+            potential.virial_sum(),
+        )
+    }
+
+    fn assert_pair(potential: &dyn PotentialEnergy<2>, r: Real, u: Real, force: Real) {
+        let (u_sum, f, virial) = pair_values(potential, r);
+        assert!(
+            (u_sum - u).abs() < 1e-4 * (1. + u.abs()),
+            "u({r}) = {u_sum}"
+        );
+        assert!(
+            (f - force).abs() < 1e-4 * (1. + force.abs()),
+            "f({r}) = {f}"
+        );
+        assert!((virial - force * r).abs() < 1e-4 * (1. + virial.abs()));
+    }
+
+    #[test]
+    fn wca_pair_values() {
+        let wca = Wca::new();
+        let sr6 = (1. / 1.05 as Real).powi(6);
+        let u = 4. * sr6 * (sr6 - 1.) + 1.;
+        assert_pair(&wca, 1.05, u, 48. * sr6 * (sr6 - 0.5) / 1.05);
+        assert!(pair_values(&wca, 1.1224).0 < 1e-6);
+        assert_pair(&wca, 1.2, 0., 0.);
+        let big = Wca::new().pair_table(PairTable::lorentz_berthelot(
+            &[Species {
+                sigma: 2.,
+                epsilon: 3.,
+            }],
+            1.,
+        ));
+        assert_pair(&big, 2.1, 3. * u, 3. * 48. * sr6 * (sr6 - 0.5) / 2.1);
+    }
+
+    #[test]
+    fn inverse_power_pair_values() {
+        let soft = InversePower::new(12., 1.5);
+        assert_pair(
+            &soft,
+            1.2,
+            (1.2 as Real).powi(-12),
+            12. * (1.2 as Real).powi(-13),
+        );
+        assert_pair(&soft, 1.6, 0., 0.);
+        let tail = PotentialEnergy::<3>::tail_correction(&soft, 100, 200.).unwrap();
+        // N ρ / 2 · 4π 1.5^-9 / 9
+        let energy = 100. * 0.5 * 0.5 * 4. * std::f32::consts::PI * (1.5 as Real).powi(-9) / 9.;
+        assert!((tail.energy - energy).abs() < 1e-4 * energy);
+        assert!(PotentialEnergy::<3>::tail_correction(&Wca::new(), 100, 200.).is_none());
+    }
+
+    #[test]
+    fn harmonic_pair_values() {
+        let harmonic = Harmonic::new();
+        assert_pair(&harmonic, 0.8, 0.5 * 0.04, 0.2);
+        assert_pair(&harmonic, 1.01, 0., 0.);
+    }
+
+    #[test]
+    fn wca_matches_cut_lennard_jones() {
+        let (region, mut pos) = initial_state::cubic_lattice::<3>(512, 0.9);
+        test_support::jitter(&mut pos, 0.1, 13);
+        let mut acc_wca = vec![DVector::default(); pos.len()];
+        let mut acc_lj = vec![DVector::default(); pos.len()];
+        let wca = Wca::new();
+        let lj = LennardJones::new((2 as Real).powf(1. / 6.));
+        PotentialEnergy::<3>::compute_forces(&wca, &pos, &mut acc_wca, &region);
+        PotentialEnergy::<3>::compute_forces(&lj, &pos, &mut acc_lj, &region);
+        for (a, b) in acc_wca.iter().zip(acc_lj.iter()) {
+            assert!((a - b).length() < 1e-3 * (1. + a.length()));
+        }
+        let u_wca = PotentialEnergy::<3>::u_sum(&wca);
+        assert!((u_wca - PotentialEnergy::<3>::u_sum(&lj)).abs() < 1e-4 * u_wca);
+    }
+}
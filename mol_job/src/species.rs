@@ -0,0 +1,132 @@
+#![allow(unused, dead_code)]
+
+use d_vector::Real;
+
+/// Lennard-Jones size and well depth of a single particle type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Species {
+    pub sigma: Real,
+    pub epsilon: Real,
+}
+
+impl Default for Species {
+    fn default() -> Self {
+        Self {
+            sigma: 1.,
+            epsilon: 1.,
+        }
+    }
+}
+
+/// Interaction parameters of one pair of types.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PairParameters {
+    pub sigma: Real,
+    pub epsilon: Real,
+    pub r_cut: Real,
+}
+
+/// Symmetric matrix of pair parameters indexed by particle type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairTable {
+    n_types: usize,
+    pairs: Vec<PairParameters>,
+}
+
+impl PairTable {
+    /// A single type with `σ = ε = 1`.
+    pub fn single(r_cut: Real) -> Self {
+        Self {
+            n_types: 1,
+            pairs: vec![PairParameters {
+                sigma: 1.,
+                epsilon: 1.,
+                r_cut,
+            }],
+        }
+    }
+
+    /// Lorentz–Berthelot mixing, `σ_ij = (σ_i + σ_j) / 2` and `ε_ij = √(ε_i ε_j)`,
+    /// with the cutoff given in units of `σ_ij`.
+    pub fn lorentz_berthelot(species: &[Species], r_cut: Real) -> Self {
+        let n_types = species.len().max(1);
+        let mut table = Self {
+            n_types,
+            pairs: vec![PairTable::single(r_cut).pairs[0]; n_types * n_types],
+        };
+        for (i, a) in species.iter().enumerate() {
+            for (j, b) in species.iter().enumerate() {
+                let sigma = 0.5 * (a.sigma + b.sigma);
+                table.pairs[i * n_types + j] = PairParameters {
+                    sigma,
+                    epsilon: (a.epsilon * b.epsilon).sqrt(),
+                    r_cut: r_cut * sigma,
+                };
+            }
+        }
+        table
+    }
+
+    /// The Kob–Andersen 80:20 mixture, type 0 being the majority A particles.
+    pub fn kob_andersen(r_cut: Real) -> Self {
+        let pair = |sigma: Real, epsilon| PairParameters {
+            sigma,
+            epsilon,
+            r_cut: r_cut * sigma,
+        };
+        Self::lorentz_berthelot(&[Species::default(); 2], r_cut)
+            .pair(0, 1, pair(0.8, 1.5))
+            .pair(1, 1, pair(0.88, 0.5))
+    }
+
+    /// Overrides the parameters of the `i`–`j` pair, in both orders.
+    pub fn pair(mut self, i: usize, j: usize, pair: PairParameters) -> Self {
+        assert!(i < self.n_types && j < self.n_types);
+        self.pairs[i * self.n_types + j] = pair;
+        self.pairs[j * self.n_types + i] = pair;
+        self
+    }
+
+    pub fn n_types(&self) -> usize {
+        self.n_types
+    }
+
+    pub fn get(&self, i: usize, j: usize) -> &PairParameters {
+        &self.pairs[i * self.n_types + j]
+    }
+
+    pub fn max_r_cut(&self) -> Real {
+        self.pairs.iter().fold(0., |r, pair| r.max(pair.r_cut))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mixing_rules() {
+        let species = [
+            Species::default(),
+            Species {
+                sigma: 2.,
+                epsilon: 4.,
+            },
+        ];
+        let table = PairTable::lorentz_berthelot(&species, 2.5);
+        assert_eq!(2, table.n_types());
+        let mixed = table.get(1, 0);
+        assert_eq!((1.5, 2., 3.75), (mixed.sigma, mixed.epsilon, mixed.r_cut));
+        assert_eq!(5., table.max_r_cut());
+    }
+
+    #[test]
+    fn explicit_overrides() {
+        let table = PairTable::kob_andersen(2.5);
+        assert_eq!(table.get(0, 1), table.get(1, 0));
+        assert_eq!(1.5, table.get(1, 0).epsilon);
+        assert_eq!(0.88, table.get(1, 1).sigma);
+        assert_eq!(1., table.get(0, 0).sigma);
+        assert_eq!(2.5, table.max_r_cut());
+    }
+}
@@ -6,10 +6,22 @@ use std::{
     fmt::Debug,
 };
 
-pub trait MolecularState<const D: usize>: Debug {
-    fn get_pos(&self) -> RefMut<Vec<DVector<D>>>;
-    fn get_vel(&self) -> RefMut<Vec<DVector<D>>>;
-    fn get_acc(&self) -> RefMut<Vec<DVector<D>>>;
+pub trait MolecularState<const D: usize>: Debug + Send {
+    fn get_pos(&self) -> RefMut<'_, Vec<DVector<D>>>;
+    fn get_vel(&self) -> RefMut<'_, Vec<DVector<D>>>;
+    fn get_acc(&self) -> RefMut<'_, Vec<DVector<D>>>;
+    /// Particle type of every molecule; empty or `None` when all are of type 0.
+    fn get_types(&self) -> Option<RefMut<'_, Vec<usize>>> {
+        None
+    }
+    /// Mass of every molecule; empty or `None` when all have unit mass.
+    fn get_masses(&self) -> Option<RefMut<'_, Vec<Real>>> {
+        None
+    }
+    /// Electric charge of every molecule; empty or `None` when all are neutral.
+    fn get_charges(&self) -> Option<RefMut<'_, Vec<Real>>> {
+        None
+    }
     fn sync(&self, time_now: Real) {}
 }
 
@@ -18,18 +30,46 @@ pub struct State<const D: usize> {
     pos: RefCell<Vec<DVector<D>>>,
     vel: RefCell<Vec<DVector<D>>>,
     acc: RefCell<Vec<DVector<D>>>,
+    #[serde(default)]
+    types: RefCell<Vec<usize>>,
+    #[serde(default)]
+    masses: RefCell<Vec<Real>>,
+    #[serde(default)]
+    charges: RefCell<Vec<Real>>,
+}
+
+/// The values of an optional per-molecule field, empty if the state has none.
+pub fn or_empty<'a, T>(values: &'a Option<RefMut<'_, Vec<T>>>) -> &'a [T] {
+    values.as_deref().map_or(&[], Vec::as_slice)
+}
+
+/// Mass of molecule `j`, unit if `masses` is empty.
+pub fn mass(masses: &[Real], j: usize) -> Real {
+    masses.get(j).copied().unwrap_or(1.)
 }
 
 impl<const D: usize> MolecularState<D> for State<D> {
-    fn get_pos(&self) -> RefMut<Vec<DVector<D>>> {
+    fn get_pos(&self) -> RefMut<'_, Vec<DVector<D>>> {
         self.pos.borrow_mut()
     }
 
-    fn get_vel(&self) -> RefMut<Vec<DVector<D>>> {
+    fn get_vel(&self) -> RefMut<'_, Vec<DVector<D>>> {
         self.vel.borrow_mut()
     }
 
-    fn get_acc(&self) -> RefMut<Vec<DVector<D>>> {
+    fn get_acc(&self) -> RefMut<'_, Vec<DVector<D>>> {
         self.acc.borrow_mut()
     }
+
+    fn get_types(&self) -> Option<RefMut<'_, Vec<usize>>> {
+        Some(self.types.borrow_mut())
+    }
+
+    fn get_masses(&self) -> Option<RefMut<'_, Vec<Real>>> {
+        Some(self.masses.borrow_mut())
+    }
+
+    fn get_charges(&self) -> Option<RefMut<'_, Vec<Real>>> {
+        Some(self.charges.borrow_mut())
+    }
 }
@@ -0,0 +1,146 @@
+#![allow(unused, dead_code)]
+
+use d_vector::Real;
+
+/// One level of the Flyvbjerg–Petersen transformation: the standard error of the
+/// mean estimated from `n_blocks` block averages of `block_size` samples each.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockLevel {
+    pub block_size: usize,
+    pub n_blocks: usize,
+    pub error: Real,
+    pub error_of_error: Real,
+}
+
+/// Summary of a correlated time series. `inefficiency` is the statistical
+/// inefficiency `s = n σ_mean² / σ²`, so that `n / s` samples are effectively independent.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SeriesStats {
+    pub n: usize,
+    pub mean: Real,
+    pub std_dev: Real,
+    pub error: Real,
+    pub inefficiency: Real,
+    pub effective_samples: Real,
+}
+
+fn mean_and_variance(values: &[f64]) -> (f64, f64) {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / n;
+    (mean, variance)
+}
+
+/// Repeatedly halves the series by averaging neighboring pairs, down to two blocks.
+pub fn blocking(series: &[Real]) -> Vec<BlockLevel> {
+    let mut values: Vec<f64> = series.iter().map(|v| *v as f64).collect();
+    let mut block_size = 1;
+    let mut levels = Vec::new();
+    while values.len() >= 2 {
+        let n = values.len() as f64;
+        let (_, variance) = mean_and_variance(&values);
+        let error = (variance / (n - 1.)).sqrt();
+        levels.push(BlockLevel {
+            block_size,
+            n_blocks: values.len(),
+            error: error as Real,
+            error_of_error: (error / (2. * (n - 1.)).sqrt()) as Real,
+        });
+        values = values
+            .chunks_exact(2)
+            .map(|pair| 0.5 * (pair[0] + pair[1]))
+            .collect();
+        block_size *= 2;
+    }
+    levels
+}
+
+/// Error of the mean at the first blocking level where the estimate stops growing,
+/// i.e. agrees with every later level having at least four blocks within its own
+/// uncertainty; the largest estimate if no such plateau is reached.
+pub fn blocking_error(series: &[Real]) -> Option<Real> {
+    let levels: Vec<_> = blocking(series)
+        .into_iter()
+        .filter(|level| level.n_blocks >= 4)
+        .collect();
+    let plateau = levels.iter().enumerate().find(|(l, level)| {
+        levels[l + 1..].iter().all(|next| {
+            (next.error - level.error).abs() <= level.error_of_error + next.error_of_error
+        })
+    });
+    match plateau {
+        Some((_, level)) => Some(level.error),
+        None => levels.iter().map(|level| level.error).reduce(Real::max),
+    }
+}
+
+pub fn series_stats(series: &[Real]) -> Option<SeriesStats> {
+    if series.is_empty() {
+        return None;
+    }
+    let values: Vec<f64> = series.iter().map(|v| *v as f64).collect();
+    let (mean, variance) = mean_and_variance(&values);
+    let n = series.len();
+    let naive = (variance / (n.max(2) - 1) as f64).sqrt() as Real;
+    let error = blocking_error(series).unwrap_or(naive);
+    let inefficiency = if variance > 0. {
+        (n as f64 * (error as f64).powi(2) / variance).max(1.)
+    } else {
+        1.
+    };
+    Some(SeriesStats {
+        n,
+        mean: mean as Real,
+        std_dev: variance.sqrt() as Real,
+        error,
+        inefficiency: inefficiency as Real,
+        effective_samples: (n as f64 / inefficiency) as Real,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use rand_distr::StandardNormal;
+
+    /// AR(1) series with correlation `phi`, whose inefficiency is `(1 + phi) / (1 - phi)`.
+    fn correlated_series(phi: Real, n: usize) -> Vec<Real> {
+        let mut rng = StdRng::seed_from_u64(17);
+        let mut x = 0.;
+        (0..n)
+            .map(|_| {
+                let noise: Real = rng.sample(StandardNormal);
+                x = phi * x + noise;
+                x
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blocking_levels() {
+        let levels = blocking(&[1., 3., 1., 3., 1., 3., 1., 3.]);
+        assert_eq!(3, levels.len());
+        assert_eq!(
+            vec![1, 2, 4],
+            levels.iter().map(|l| l.block_size).collect::<Vec<_>>()
+        );
+        assert_eq!(0., levels[1].error);
+    }
+
+    #[test]
+    fn uncorrelated_series() {
+        let stats = series_stats(&correlated_series(0., 1 << 14)).unwrap();
+        assert!((stats.inefficiency - 1.).abs() < 0.3);
+        assert!((stats.error - stats.std_dev / (stats.n as Real).sqrt()).abs() < 0.3 * stats.error);
+    }
+
+    #[test]
+    fn correlated_series_inefficiency() {
+        let stats = series_stats(&correlated_series(0.9, 1 << 16)).unwrap();
+        assert!((stats.inefficiency - 19.).abs() < 5.);
+        assert!(
+            (stats.effective_samples - stats.n as Real / 19.).abs() < 0.3 * stats.effective_samples
+        );
+    }
+}
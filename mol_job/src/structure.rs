@@ -0,0 +1,324 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    boundaries::{BoundaryConditions, Region},
+    potential::PotentialEnergy,
+    prop::{PropSummary, PropTable, Props},
+    track,
+};
+use d_vector::{DVector, Real};
+use std::{
+    cell::{Cell, RefCell},
+    f32::consts::TAU,
+    path::Path,
+};
+
+/// Integer indices `n` of the wavevectors `k = 2π n / L` with
+/// `|k| <= 2π n_max / L_max`, one of every `±k` pair.
+pub fn wave_indices<const D: usize>(size: &[Real; D], n_max: usize) -> Vec<[i32; D]> {
+    let l_max = size.iter().fold(0 as Real, |l, s| l.max(*s));
+    let k_max = TAU * n_max as Real / l_max;
+    let side = 2 * n_max + 1;
+    let mut result = Vec::new();
+    for index in 0..side.pow(D as u32) {
+        let mut n = [0; D];
+        let mut rest = index;
+        for c in n.iter_mut() {
+            *c = (rest % side) as i32 - n_max as i32;
+            rest /= side;
+        }
+        let leading = n.iter().find(|c| **c != 0);
+        if leading.is_some_and(|c| *c > 0) && wave_vector(size, &n).length() <= k_max * 1.0001 {
+            result.push(n);
+        }
+    }
+    result
+}
+
+pub fn wave_vector<const D: usize>(size: &[Real; D], n: &[i32; D]) -> DVector<D> {
+    let mut k = [0 as Real; D];
+    for ((k, n), l) in k.iter_mut().zip(n.iter()).zip(size.iter()) {
+        *k = TAU * *n as Real / l;
+    }
+    DVector::from(k)
+}
+
+/// `S(k) = |Σ_j exp(i k · r_j)|² / N` for a single wavevector.
+pub fn structure_factor<const D: usize>(pos: &[DVector<D>], k: &DVector<D>) -> Real {
+    let (mut re, mut im) = (0 as Real, 0 as Real);
+    for r in pos.iter() {
+        let phase: Real = k
+            .components()
+            .iter()
+            .zip(r.components())
+            .map(|(k, r)| k * r)
+            .sum();
+        re += phase.cos();
+        im += phase.sin();
+    }
+    (re * re + im * im) / pos.len().max(1) as Real
+}
+
+/// Static structure factor on the reciprocal lattice of the periodic box,
+/// averaged over shells of `|k|` of width `shell_width`. A watched wavevector,
+/// given by its integer indices, is also recorded against time.
+#[derive(Debug)]
+pub struct StructureFactor<const D: usize> {
+    n_max: usize,
+    shell_width: Real,
+    watch: Option<[i32; D]>,
+    every: usize,
+    step_avg: usize,
+    steps: Cell<usize>,
+    step_count: Cell<usize>,
+    shell_k: RefCell<Vec<Real>>,
+    shell_s: RefCell<Vec<Real>>,
+    shell_count: RefCell<Vec<usize>>,
+    history: RefCell<Vec<[Real; 2]>>,
+    last: RefCell<Option<PropSummary>>,
+}
+
+impl<const D: usize> StructureFactor<D> {
+    pub fn new(n_max: usize, shell_width: Real) -> Self {
+        Self {
+            n_max: n_max.max(1),
+            shell_width,
+            watch: None,
+            every: 1,
+            step_avg: 1000,
+            steps: Cell::new(0),
+            step_count: Cell::new(0),
+            shell_k: RefCell::new(Vec::new()),
+            shell_s: RefCell::new(Vec::new()),
+            shell_count: RefCell::new(Vec::new()),
+            history: RefCell::new(Vec::new()),
+            last: RefCell::new(None),
+        }
+    }
+
+    pub fn watch(mut self, n: [i32; D]) -> Self {
+        self.watch = Some(n);
+        self
+    }
+
+    pub fn every(mut self, steps: usize) -> Self {
+        self.every = steps.max(1);
+        self
+    }
+
+    pub fn step_avg(mut self, steps: usize) -> Self {
+        self.step_avg = steps.max(1);
+        self
+    }
+
+    /// Adds one configuration in a box of the given size, taken at `time`.
+    pub fn sample(&self, pos: &[DVector<D>], size: &[Real; D], time: Real) {
+        let n_max = self.n_max as i32;
+        let side = 2 * self.n_max + 1;
+        // exp(i 2π n x_a / L_a) for every molecule, axis and -n_max <= n <= n_max
+        let mut phases = vec![(0 as Real, 0 as Real); pos.len() * D * side];
+        for (j, r) in pos.iter().enumerate() {
+            for (a, (x, l)) in r.components().iter().zip(size.iter()).enumerate() {
+                let base = (j * D + a) * side;
+                for n in -n_max..=n_max {
+                    let phase = TAU * n as Real * x / l;
+                    phases[base + (n + n_max) as usize] = (phase.cos(), phase.sin());
+                }
+            }
+        }
+        let mut shell_k = self.shell_k.borrow_mut();
+        let mut shell_s = self.shell_s.borrow_mut();
+        let mut shell_count = self.shell_count.borrow_mut();
+        for n in wave_indices(size, self.n_max) {
+            let (mut re, mut im) = (0 as Real, 0 as Real);
+            for j in 0..pos.len() {
+                let (mut pr, mut pi) = (1 as Real, 0 as Real);
+                for (a, n) in n.iter().enumerate() {
+                    let (cr, ci) = phases[(j * D + a) * side + (n + n_max) as usize];
+                    (pr, pi) = (pr * cr - pi * ci, pr * ci + pi * cr);
+                }
+                re += pr;
+                im += pi;
+            }
+            let k = wave_vector(size, &n).length();
+            let bin = (k / self.shell_width) as usize;
+            if shell_s.len() <= bin {
+                shell_k.resize(bin + 1, 0.);
+                shell_s.resize(bin + 1, 0.);
+                shell_count.resize(bin + 1, 0);
+            }
+            shell_k[bin] += k;
+            shell_s[bin] += (re * re + im * im) / pos.len().max(1) as Real;
+            shell_count[bin] += 1;
+        }
+        if let Some(n) = &self.watch {
+            let s = structure_factor(pos, &wave_vector(size, n));
+            self.history.borrow_mut().push([time, s]);
+        }
+    }
+
+    /// Columns `k` (mean `|k|` of the shell) and `s`; empty shells are left out.
+    pub fn table(&self) -> PropTable {
+        let mut table = PropTable::new("sk", &["k", "s"]);
+        let shell_k = self.shell_k.borrow();
+        let shell_s = self.shell_s.borrow();
+        for ((k, s), count) in shell_k
+            .iter()
+            .zip(shell_s.iter())
+            .zip(self.shell_count.borrow().iter())
+        {
+            if *count > 0 {
+                table
+                    .rows
+                    .push(vec![k / *count as Real, s / *count as Real]);
+            }
+        }
+        table
+    }
+
+    /// Columns `t` and `s` of the watched wavevector, since the last reset.
+    pub fn history(&self) -> PropTable {
+        let mut table = PropTable::new("sk_watch", &["t", "s"]);
+        table.rows = self
+            .history
+            .borrow()
+            .iter()
+            .map(|row| row.to_vec())
+            .collect();
+        table
+    }
+
+    fn tables(&self) -> Vec<PropTable> {
+        let mut tables = vec![self.table()];
+        if self.watch.is_some() {
+            tables.push(self.history());
+        }
+        tables
+    }
+}
+
+impl<const D: usize> Props<D> for StructureFactor<D> {
+    fn reset(&self) {
+        self.shell_k.borrow_mut().clear();
+        self.shell_s.borrow_mut().clear();
+        self.shell_count.borrow_mut().clear();
+        self.history.borrow_mut().clear();
+    }
+
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<D>,
+        pos: &[DVector<D>],
+        vel: &[DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        unwrapped: &[DVector<D>],
+        delta_t: Real,
+        time: Real,
+    ) {
+        self.steps.set(self.steps.get() + 1);
+        if !self.steps.get().is_multiple_of(self.every) {
+            return;
+        }
+        if let Some(size) = boundaries.box_size() {
+            self.sample(pos, size, time);
+        }
+    }
+
+    fn accum_props(&self) {}
+
+    fn need_avg(&self, step_count: usize) -> bool {
+        self.step_count.set(step_count);
+        step_count.is_multiple_of(self.step_avg)
+    }
+
+    fn avg_props(&self) {
+        *self.last.borrow_mut() = Some(PropSummary {
+            step_count: self.step_count.get(),
+            entries: Vec::new(),
+            tables: self.tables(),
+        });
+    }
+
+    fn summary(&self) -> Option<PropSummary> {
+        self.last.borrow().clone()
+    }
+}
+
+/// Runs `structure_factor` over every frame of a saved track; the track does not
+/// record the box, so `region` must be the one the job ran in.
+pub fn track_structure_factor<P: AsRef<Path>>(
+    path: P,
+    region: &Region<3>,
+    structure_factor: &StructureFactor<3>,
+) -> std::io::Result<Vec<PropTable>> {
+    for (time, pos) in track::read_frames(path)? {
+        structure_factor.sample(&pos, region.dimensions(), time);
+    }
+    Ok(structure_factor.tables())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        initial_state,
+        job::{Job, JobSetup},
+        state::{MolecularState, State},
+        test_support,
+    };
+    use std::io::Write;
+
+    #[test]
+    fn half_space_indices() {
+        assert_eq!(vec![[1, 0], [0, 1]], wave_indices(&[5., 5.], 1));
+        assert_eq!(3, wave_indices(&[5., 5., 5.], 1).len());
+    }
+
+    #[test]
+    fn lattice_peaks() {
+        let (region, pos) = initial_state::cubic_lattice::<3>(512, 1.);
+        let sk = StructureFactor::new(3, 0.2).watch([8, 0, 0]);
+        sk.sample(&pos, region.dimensions(), 0.);
+        for s in sk.table().column("s").unwrap() {
+            assert!(s < 1e-3);
+        }
+        assert!((sk.history().rows[0][1] - 512.).abs() < 1.);
+    }
+
+    #[test]
+    fn watch_history_follows_job_time() {
+        let (region, pos) = initial_state::cubic_lattice::<3>(216, 0.8);
+        let mut job: Job<3> = JobSetup::build()
+            .boundaries(region)
+            .init_pos(pos)
+            .random_vel(1.)
+            .props(StructureFactor::new(2, 0.5).watch([1, 0, 0]).step_avg(10))
+            .job();
+        job.run(25);
+        let history = job.props().summary().unwrap().tables[1].clone();
+        assert_eq!(10, history.rows.len());
+        assert!((history.rows[9][0] - 20. * 0.005).abs() < 1e-6);
+        assert!((history.rows[0][0] - 11. * 0.005).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ideal_gas_from_track() {
+        let path = test_support::temp_path("sk_track.txt");
+        let mut file = std::fs::File::create(&path).unwrap();
+        for frame in 0..10 {
+            let state = State::<3>::default();
+            *state.get_pos() = test_support::random_pos(500, 8., frame);
+            let json = serde_json::to_string(&state).unwrap();
+            writeln!(file, "{}. {}", frame as Real * 0.5, json).unwrap();
+        }
+        let sk = StructureFactor::new(4, 0.5).watch([1, 0, 0]);
+        let tables = track_structure_factor(&path, &Region::new([8.; 3]), &sk).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let s = tables[0].column("s").unwrap();
+        let mean = s.iter().sum::<Real>() / s.len() as Real;
+        assert!((mean - 1.).abs() < 0.1);
+        assert_eq!(10, tables[1].rows.len());
+        assert_eq!(4.5, tables[1].rows[9][0]);
+    }
+}
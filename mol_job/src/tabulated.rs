@@ -0,0 +1,202 @@
+#![allow(unused, dead_code)]
+
+use crate::pair::PairPotential;
+use d_vector::Real;
+use std::{
+    fs::OpenOptions,
+    io::{self, BufRead, BufReader},
+    path::Path,
+};
+
+/// Natural cubic spline through points with increasing `x`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CubicSpline {
+    x: Vec<Real>,
+    y: Vec<Real>,
+    second: Vec<Real>,
+}
+
+impl CubicSpline {
+    pub fn new(x: Vec<Real>, y: Vec<Real>) -> Self {
+        let n = x.len();
+        assert!(n >= 2 && n == y.len());
+        assert!(x.windows(2).all(|w| w[0] < w[1]));
+        // Tridiagonal system for the second derivatives, zero at both ends
+        let h: Vec<f64> = x.windows(2).map(|w| (w[1] - w[0]) as f64).collect();
+        let slope: Vec<f64> = y
+            .windows(2)
+            .zip(h.iter())
+            .map(|(w, h)| (w[1] - w[0]) as f64 / h)
+            .collect();
+        let mut diag = vec![1.; n];
+        let mut rhs = vec![0.; n];
+        for i in 1..n - 1 {
+            let lower = if i > 1 { h[i - 1] / diag[i - 1] } else { 0. };
+            diag[i] = 2. * (h[i - 1] + h[i]) - lower * h[i - 1];
+            rhs[i] = 6. * (slope[i] - slope[i - 1]) - lower * rhs[i - 1];
+        }
+        let mut second = vec![0.; n];
+        for i in (1..n - 1).rev() {
+            second[i] = (rhs[i] - h[i] * second[i + 1]) / diag[i];
+        }
+        Self {
+            x,
+            y,
+            second: second.into_iter().map(|m| m as Real).collect(),
+        }
+    }
+
+    /// Interval containing `x` and the weights of its two ends; points outside the
+    /// table use the cubic of the nearest interval.
+    fn interval(&self, x: Real) -> (usize, Real, Real, Real) {
+        let i = self
+            .x
+            .partition_point(|xi| *xi <= x)
+            .clamp(1, self.x.len() - 1)
+            - 1;
+        let h = self.x[i + 1] - self.x[i];
+        (i, h, (self.x[i + 1] - x) / h, (x - self.x[i]) / h)
+    }
+
+    pub fn value(&self, x: Real) -> Real {
+        let (i, h, a, b) = self.interval(x);
+        let (m0, m1) = (self.second[i], self.second[i + 1]);
+        a * self.y[i]
+            + b * self.y[i + 1]
+            + ((a * a * a - a) * m0 + (b * b * b - b) * m1) * h * h / 6.
+    }
+
+    pub fn derivative(&self, x: Real) -> Real {
+        let (i, h, a, b) = self.interval(x);
+        let (m0, m1) = (self.second[i], self.second[i + 1]);
+        (self.y[i + 1] - self.y[i]) / h + ((3. * b * b - 1.) * m1 - (3. * a * a - 1.) * m0) * h / 6.
+    }
+
+    pub fn x_max(&self) -> Real {
+        self.x[self.x.len() - 1]
+    }
+}
+
+/// Pair potential interpolated from a table of `u(r)`, cut at its last distance.
+/// Forces come from the derivative of the spline, so they stay consistent with the energy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tabulated {
+    spline: CubicSpline,
+}
+
+impl Tabulated {
+    pub fn new(r: Vec<Real>, u: Vec<Real>) -> Self {
+        Self {
+            spline: CubicSpline::new(r, u),
+        }
+    }
+
+    /// Reads whitespace separated `r u` columns, skipping blank lines and `#` comments;
+    /// further columns are ignored.
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let input = OpenOptions::new().read(true).open(path)?;
+        let (mut r, mut u) = (Vec::new(), Vec::new());
+        for (n, line) in BufReader::new(input).lines().enumerate() {
+            let line = line?;
+            let line = line.split('#').next().unwrap_or_default();
+            let mut columns = line.split_whitespace().map(str::parse::<Real>);
+            match (columns.next(), columns.next()) {
+                (None, _) => continue,
+                (Some(Ok(r_value)), Some(Ok(u_value))) => {
+                    r.push(r_value);
+                    u.push(u_value);
+                }
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("line {}: expected r and u", n + 1),
+                    ))
+                }
+            }
+        }
+        if r.len() < 2 || r.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "need at least two rows with increasing r",
+            ));
+        }
+        Ok(Self::new(r, u))
+    }
+}
+
+impl PairPotential for Tabulated {
+    fn energy(&self, rr: Real) -> Real {
+        self.spline.value(rr.sqrt())
+    }
+
+    fn force_over_r(&self, rr: Real) -> Real {
+        let r = rr.sqrt();
+        -self.spline.derivative(r) / r
+    }
+
+    fn r_cut(&self) -> Real {
+        self.spline.x_max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cutoff::Cutoff, initial_state, lennard_jones::LennardJones, pair::Pairwise,
+        potential::PotentialEnergy, test_support,
+    };
+    use d_vector::DVector;
+    use std::io::Write;
+
+    #[test]
+    fn spline_interpolation() {
+        let x: Vec<Real> = (0..=40).map(|i| 0.1 * i as Real).collect();
+        let y = x.iter().map(|x| x.sin()).collect();
+        let spline = CubicSpline::new(x, y);
+        for x in [0.05, 1.23, 2.5, 3.5] {
+            assert!((spline.value(x) - x.sin()).abs() < 1e-4);
+            assert!((spline.derivative(x) - x.cos()).abs() < 2e-3);
+        }
+        let line = CubicSpline::new(vec![0., 1., 3.], vec![1., 3., 7.]);
+        assert!((line.value(2.) - 5.).abs() < 1e-6);
+        assert!((line.derivative(-1.) - 2.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn tabulated_lennard_jones() {
+        let path = std::env::temp_dir().join("mol_job_lj_table.txt");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(file, "# r u").unwrap();
+        for i in 0..=340 {
+            let r = 0.8 + 0.005 * i as Real;
+            let sr6 = r.powi(-6);
+            writeln!(file, "{r} {}", 4. * sr6 * (sr6 - 1.)).unwrap();
+        }
+        drop(file);
+        let table = Pairwise::with_potential(Tabulated::load(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        let (region, mut pos) = initial_state::cubic_lattice::<3>(512, 0.8);
+        test_support::jitter(&mut pos, 0.1, 15);
+        let lj = LennardJones::new(2.5).cutoff(Cutoff::Truncated);
+        let mut acc_table = vec![DVector::default(); pos.len()];
+        let mut acc_lj = vec![DVector::default(); pos.len()];
+        PotentialEnergy::<3>::compute_forces(&table, &pos, &mut acc_table, &region);
+        PotentialEnergy::<3>::compute_forces(&lj, &pos, &mut acc_lj, &region);
+        for (a, b) in acc_lj.iter().zip(acc_table.iter()) {
+            assert!((a - b).length() < 1e-2 * (1. + a.length()));
+        }
+        let u_lj = PotentialEnergy::<3>::u_sum(&lj);
+        assert!((PotentialEnergy::<3>::u_sum(&table) - u_lj).abs() < 1e-3 * u_lj.abs());
+    }
+
+    #[test]
+    fn malformed_table() {
+        let path = std::env::temp_dir().join("mol_job_bad_table.txt");
+        std::fs::write(&path, "1.0 2.0\n1.5 x\n").unwrap();
+        let error = Tabulated::load(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+    }
+}
@@ -0,0 +1,53 @@
+#![allow(unused, dead_code)]
+
+use crate::{boundaries::BoundaryConditions, potential::PotentialEnergy};
+use atomic_float::AtomicF32;
+use d_vector::{DVector, Real};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{path::PathBuf, sync::atomic::Ordering};
+
+/// `n` points uniform in `[-side / 2, side / 2)` along every axis, the same for every
+/// run with the same `seed`.
+pub fn random_pos<const D: usize>(n: usize, side: Real, seed: u64) -> Vec<DVector<D>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..n)
+        .map(|_| DVector::from([0.; D].map(|_: Real| side * (rng.gen::<Real>() - 0.5))))
+        .collect()
+}
+
+/// Moves every position by up to `amplitude / 2` along each axis, reproducibly.
+pub fn jitter<const D: usize>(pos: &mut [DVector<D>], amplitude: Real, seed: u64) {
+    let shifts = random_pos(pos.len(), amplitude, seed);
+    for (p, shift) in pos.iter_mut().zip(shifts) {
+        *p += &shift;
+    }
+}
+
+/// File `name` in the temporary directory, unique to this test process.
+pub fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("mol_job_{}_{name}", std::process::id()))
+}
+
+/// Unit spring pulling every particle to the origin, `u = x² / 2`.
+#[derive(Debug, Default)]
+pub struct Spring(AtomicF32);
+
+impl PotentialEnergy<1> for Spring {
+    fn compute_forces(
+        &self,
+        pos: &[DVector<1>],
+        acc: &mut [DVector<1>],
+        _: &dyn BoundaryConditions<1>,
+    ) {
+        let mut u_sum = 0.;
+        for (position, acceleration) in pos.iter().zip(acc.iter_mut()) {
+            *acceleration = -1. * position;
+            u_sum += 0.5 * position.square_length();
+        }
+        self.0.store(u_sum, Ordering::SeqCst);
+    }
+
+    fn u_sum(&self) -> Real {
+        self.0.load(Ordering::SeqCst)
+    }
+}
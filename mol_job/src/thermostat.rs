@@ -0,0 +1,382 @@
+#![allow(unused, dead_code)]
+
+use crate::state;
+use d_vector::{DVector, Real};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand_distr::{ChiSquared, Distribution, StandardNormal};
+use std::{fmt::Debug, ops::MulAssign};
+
+pub trait Thermostat<const D: usize>: Debug + Send {
+    fn before_step(&mut self, delta_t: Real, vel: &mut [DVector<D>], masses: &[Real]) {}
+    fn after_step(&mut self, delta_t: Real, vel: &mut [DVector<D>], masses: &[Real]);
+    /// Energy of the thermostat itself, so that `K + U + conserved_energy()`
+    /// stays constant along the thermostatted trajectory.
+    fn conserved_energy(&self) -> Real {
+        0.0
+    }
+}
+
+/// `Σ m v² / 2`, with unit masses if `masses` is empty.
+pub fn kinetic_energy<const D: usize>(vel: &[DVector<D>], masses: &[Real]) -> Real {
+    0.5 * vel
+        .iter()
+        .enumerate()
+        .map(|(j, v)| state::mass(masses, j) * v.square_length())
+        .sum::<Real>()
+}
+
+pub fn degrees_of_freedom<const D: usize>(n_mol: usize) -> Real {
+    (D * n_mol.saturating_sub(1)) as Real
+}
+
+pub fn temperature<const D: usize>(vel: &[DVector<D>], masses: &[Real]) -> Real {
+    let n_f = degrees_of_freedom::<D>(vel.len());
+    if n_f > 0. {
+        2. * kinetic_energy(vel, masses) / n_f
+    } else {
+        0.
+    }
+}
+
+fn scale_velocities<const D: usize>(vel: &mut [DVector<D>], factor: Real) {
+    for velocity in vel.iter_mut() {
+        *velocity = factor * &*velocity;
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct NoThermostat;
+
+impl<const D: usize> Thermostat<D> for NoThermostat {
+    fn after_step(&mut self, _: Real, _: &mut [DVector<D>], _: &[Real]) {}
+}
+
+/// Rescales velocities to the exact target temperature every `every` steps.
+#[derive(Debug)]
+pub struct Rescale {
+    temperature: Real,
+    every: usize,
+    steps: usize,
+    heat: Real,
+}
+
+impl Rescale {
+    pub fn new(temperature: Real) -> Self {
+        Self {
+            temperature,
+            every: 1,
+            steps: 0,
+            heat: 0.,
+        }
+    }
+
+    pub fn every(mut self, steps: usize) -> Self {
+        self.every = steps.max(1);
+        self
+    }
+}
+
+impl<const D: usize> Thermostat<D> for Rescale {
+    fn after_step(&mut self, _: Real, vel: &mut [DVector<D>], masses: &[Real]) {
+        self.steps += 1;
+        if !self.steps.is_multiple_of(self.every) {
+            return;
+        }
+        let t = temperature(vel, masses);
+        if t > 0. {
+            let k = kinetic_energy(vel, masses);
+            scale_velocities(vel, (self.temperature / t).sqrt());
+            self.heat += k - kinetic_energy(vel, masses);
+        }
+    }
+
+    fn conserved_energy(&self) -> Real {
+        self.heat
+    }
+}
+
+#[derive(Debug)]
+pub struct Berendsen {
+    temperature: Real,
+    tau: Real,
+    heat: Real,
+}
+
+impl Berendsen {
+    pub fn new(temperature: Real, tau: Real) -> Self {
+        Self {
+            temperature,
+            tau,
+            heat: 0.,
+        }
+    }
+}
+
+impl<const D: usize> Thermostat<D> for Berendsen {
+    fn after_step(&mut self, delta_t: Real, vel: &mut [DVector<D>], masses: &[Real]) {
+        let t = temperature(vel, masses);
+        if t > 0. {
+            let k = kinetic_energy(vel, masses);
+            let lambda = (1. + delta_t / self.tau * (self.temperature / t - 1.)).sqrt();
+            scale_velocities(vel, lambda);
+            self.heat += k - kinetic_energy(vel, masses);
+        }
+    }
+
+    fn conserved_energy(&self) -> Real {
+        self.heat
+    }
+}
+
+/// Nosé–Hoover chain propagated by the Martyna–Tuckerman–Klein scheme,
+/// half a step before and half a step after every integrator step.
+#[derive(Debug)]
+pub struct NoseHoover {
+    temperature: Real,
+    tau: Real,
+    xi: Vec<Real>,
+    v_xi: Vec<Real>,
+    n_f: Real,
+}
+
+impl NoseHoover {
+    pub fn new(temperature: Real, tau: Real) -> Self {
+        Self {
+            temperature,
+            tau,
+            xi: vec![0.; 3],
+            v_xi: vec![0.; 3],
+            n_f: 0.,
+        }
+    }
+
+    pub fn chain(mut self, length: usize) -> Self {
+        self.xi = vec![0.; length.max(1)];
+        self.v_xi = vec![0.; length.max(1)];
+        self
+    }
+
+    fn q(&self, j: usize) -> Real {
+        let q = self.temperature * self.tau * self.tau;
+        if j == 0 {
+            self.n_f * q
+        } else {
+            q
+        }
+    }
+
+    fn force(&self, j: usize, kinetic: Real) -> Real {
+        if j == 0 {
+            (2. * kinetic - self.n_f * self.temperature) / self.q(0)
+        } else {
+            (self.q(j - 1) * self.v_xi[j - 1] * self.v_xi[j - 1] - self.temperature) / self.q(j)
+        }
+    }
+
+    fn propagate<const D: usize>(
+        &mut self,
+        delta_t: Real,
+        vel: &mut [DVector<D>],
+        masses: &[Real],
+    ) {
+        self.n_f = degrees_of_freedom::<D>(vel.len());
+        if self.n_f == 0. {
+            return;
+        }
+        let m = self.v_xi.len();
+        let dt2 = delta_t / 2.;
+        let dt4 = delta_t / 4.;
+        let dt8 = delta_t / 8.;
+        let mut kinetic = kinetic_energy(vel, masses);
+
+        self.v_xi[m - 1] += dt4 * self.force(m - 1, kinetic);
+        for j in (0..m - 1).rev() {
+            let damp = (-dt8 * self.v_xi[j + 1]).exp();
+            self.v_xi[j] *= damp;
+            self.v_xi[j] += dt4 * self.force(j, kinetic);
+            self.v_xi[j] *= damp;
+        }
+
+        let scale = (-dt2 * self.v_xi[0]).exp();
+        scale_velocities(vel, scale);
+        kinetic *= scale * scale;
+        for (xi, v_xi) in self.xi.iter_mut().zip(self.v_xi.iter()) {
+            *xi += dt2 * v_xi;
+        }
+
+        for j in 0..m - 1 {
+            let damp = (-dt8 * self.v_xi[j + 1]).exp();
+            self.v_xi[j] *= damp;
+            self.v_xi[j] += dt4 * self.force(j, kinetic);
+            self.v_xi[j] *= damp;
+        }
+        self.v_xi[m - 1] += dt4 * self.force(m - 1, kinetic);
+    }
+}
+
+impl<const D: usize> Thermostat<D> for NoseHoover {
+    fn before_step(&mut self, delta_t: Real, vel: &mut [DVector<D>], masses: &[Real]) {
+        self.propagate(delta_t, vel, masses);
+    }
+
+    fn after_step(&mut self, delta_t: Real, vel: &mut [DVector<D>], masses: &[Real]) {
+        self.propagate(delta_t, vel, masses);
+    }
+
+    fn conserved_energy(&self) -> Real {
+        let mut result = 0.;
+        for (j, (xi, v_xi)) in self.xi.iter().zip(self.v_xi.iter()).enumerate() {
+            result += 0.5 * self.q(j) * v_xi * v_xi;
+            result += if j == 0 { self.n_f } else { 1. } * self.temperature * xi;
+        }
+        result
+    }
+}
+
+/// Bussi–Donadio–Parrinello canonical sampling through velocity rescaling.
+#[derive(Debug)]
+pub struct Bussi {
+    temperature: Real,
+    tau: Real,
+    rng: StdRng,
+    heat: Real,
+}
+
+impl Bussi {
+    pub fn new(temperature: Real, tau: Real) -> Self {
+        Self {
+            temperature,
+            tau,
+            rng: StdRng::from_entropy(),
+            heat: 0.,
+        }
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.rng = StdRng::seed_from_u64(seed);
+        self
+    }
+
+    fn new_kinetic(&mut self, kinetic: Real, n_f: Real, delta_t: Real) -> Real {
+        let target = 0.5 * n_f * self.temperature;
+        let c = (-delta_t / self.tau).exp();
+        let r1: Real = self.rng.sample(StandardNormal);
+        let s: Real = if n_f > 1. {
+            ChiSquared::new(n_f - 1.).unwrap().sample(&mut self.rng)
+        } else {
+            0.
+        };
+        kinetic
+            + (1. - c) * (target * (r1 * r1 + s) / n_f - kinetic)
+            + 2. * r1 * (c * (1. - c) * target * kinetic / n_f).sqrt()
+    }
+}
+
+impl<const D: usize> Thermostat<D> for Bussi {
+    fn after_step(&mut self, delta_t: Real, vel: &mut [DVector<D>], masses: &[Real]) {
+        let n_f = degrees_of_freedom::<D>(vel.len());
+        let kinetic = kinetic_energy(vel, masses);
+        if n_f == 0. || kinetic <= 0. {
+            return;
+        }
+        let new_kinetic = self.new_kinetic(kinetic, n_f, delta_t).max(0.);
+        scale_velocities(vel, (new_kinetic / kinetic).sqrt());
+        self.heat += kinetic - new_kinetic;
+    }
+
+    fn conserved_energy(&self) -> Real {
+        self.heat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        initial_state,
+        job::{Job, JobSetup},
+        lennard_jones::LennardJones,
+        species::{PairTable, Species},
+    };
+
+    fn lj_job(thermostat: impl Thermostat<3> + 'static) -> Job<3> {
+        let (boundaries, pos) = initial_state::cubic_lattice(216, 0.8);
+        JobSetup::build()
+            .delta_t(0.005)
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .random_vel(0.5)
+            .potential(LennardJones::new((2 as Real).powf(1. / 6.)))
+            .thermostat(thermostat)
+            .job()
+    }
+
+    fn mean_temperature(job: &mut Job<3>, steps: usize) -> Real {
+        let mut sum = 0.;
+        for _ in 0..steps {
+            job.run(1);
+            sum += job.temperature();
+        }
+        sum / steps as Real
+    }
+
+    #[test]
+    fn mass_weighted_temperature() {
+        let (boundaries, pos) = initial_state::cubic_lattice(216, 0.8);
+        let types = initial_state::composition(pos.len(), &[0.5, 0.5]);
+        let wca = (2 as Real).powf(1. / 6.);
+        let mut job: Job<3> = JobSetup::build()
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .types(types)
+            .type_masses(&[1., 5.])
+            .random_vel(0.5)
+            .potential(
+                LennardJones::new(wca)
+                    .pair_table(PairTable::lorentz_berthelot(&[Species::default(); 2], wca)),
+            )
+            .thermostat(Rescale::new(1.5).every(1000))
+            .job();
+        assert!(job.momentum().length() < 1e-3);
+        assert!((job.temperature() - 0.5).abs() < 0.05);
+        job.run(1);
+        let start = job.kinetic_energy() + job.potential_energy();
+        job.run(300);
+        let end = job.kinetic_energy() + job.potential_energy();
+        assert!((end - start).abs() < 1e-2 * start.abs());
+        assert!(job.momentum().length() < 1e-2);
+        job.run(699);
+        assert!((job.temperature() - 1.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rescale_and_berendsen_reach_target() {
+        let mut job = lj_job(Rescale::new(1.5));
+        job.run(1);
+        assert!((job.temperature() - 1.5).abs() < 1e-4);
+
+        let mut job = lj_job(Berendsen::new(1.5, 0.05));
+        job.run(400);
+        assert!((mean_temperature(&mut job, 200) - 1.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn nose_hoover_conserves_extended_energy() {
+        let mut job = lj_job(NoseHoover::new(1.5, 0.1));
+        job.run(10);
+        let start = job.extended_energy();
+        let mean = mean_temperature(&mut job, 600);
+        assert!((job.extended_energy() - start).abs() < 0.01 * start.abs());
+        assert!((mean - 1.5).abs() < 0.15);
+    }
+
+    #[test]
+    fn bussi_samples_target_temperature() {
+        let mut job = lj_job(Bussi::new(1.5, 0.05).seed(7));
+        job.run(10);
+        let start = job.extended_energy();
+        job.run(200);
+        assert!((mean_temperature(&mut job, 400) - 1.5).abs() < 0.1);
+        assert!((job.extended_energy() - start).abs() < 0.01 * start.abs());
+    }
+}
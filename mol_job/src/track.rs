@@ -5,7 +5,7 @@ use d_vector::{DVector, Real};
 use std::{
     cell::{Cell, RefCell, RefMut},
     fs::{File, OpenOptions},
-    io::{BufRead, BufReader, Write},
+    io::{self, BufRead, BufReader, Write},
     path::Path,
 };
 
@@ -25,18 +25,30 @@ impl Default for Track {
 }
 
 impl MolecularState<3> for Track {
-    fn get_pos(&self) -> RefMut<Vec<DVector<3>>> {
+    fn get_pos(&self) -> RefMut<'_, Vec<DVector<3>>> {
         self.inner.get_pos()
     }
 
-    fn get_vel(&self) -> RefMut<Vec<DVector<3>>> {
+    fn get_vel(&self) -> RefMut<'_, Vec<DVector<3>>> {
         self.inner.get_vel()
     }
 
-    fn get_acc(&self) -> RefMut<Vec<DVector<3>>> {
+    fn get_acc(&self) -> RefMut<'_, Vec<DVector<3>>> {
         self.inner.get_acc()
     }
 
+    fn get_types(&self) -> Option<RefMut<'_, Vec<usize>>> {
+        self.inner.get_types()
+    }
+
+    fn get_masses(&self) -> Option<RefMut<'_, Vec<Real>>> {
+        self.inner.get_masses()
+    }
+
+    fn get_charges(&self) -> Option<RefMut<'_, Vec<Real>>> {
+        self.inner.get_charges()
+    }
+
     fn sync(&self, time_now: Real) {
         let json = serde_json::to_string(&self.inner).unwrap();
         writeln!(self.output.borrow_mut(), "{}. {}", time_now, json);
@@ -44,6 +56,7 @@ impl MolecularState<3> for Track {
 }
 
 impl Track {
+    #[allow(clippy::result_large_err)]
     pub fn restore_from<P: AsRef<Path>>(path: P) -> Result<Self, Self> {
         let input = OpenOptions::new().read(true).open(path)?;
         let mut last_line = last_line_of_file(input).ok_or_else(Self::default)?;
@@ -68,12 +81,56 @@ impl From<std::io::Error> for Track {
 
 fn open_track() -> std::io::Result<File> {
     OpenOptions::new()
-        .write(true)
         .append(true)
         .create(true)
         .open("track.txt")
 }
 
+/// Reads every saved frame of a track file as `(time, positions)`; a line that is
+/// not a time followed by a saved state is an `InvalidData` error.
+pub fn read_frames<P: AsRef<Path>>(path: P) -> io::Result<Vec<(Real, Vec<DVector<3>>)>> {
+    let input = OpenOptions::new().read(true).open(path)?;
+    let mut frames = Vec::new();
+    for (n, line) in BufReader::new(input).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let frame = line.split_once(". ").and_then(|(time, json)| {
+            let state = serde_json::from_str::<State<3>>(json).ok()?;
+            let pos = state.get_pos().clone();
+            Some((time.parse().ok()?, pos))
+        });
+        match frame {
+            Some(frame) => frames.push(frame),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: expected a time and a state", n + 1),
+                ))
+            }
+        }
+    }
+    Ok(frames)
+}
+
 pub(crate) fn last_line_of_file(f: File) -> Option<String> {
-    BufReader::new(f).lines().flatten().last()
+    BufReader::new(f).lines().map_while(Result::ok).last()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    #[test]
+    fn malformed_frame() {
+        let path = test_support::temp_path("bad_track.txt");
+        let state = serde_json::to_string(&State::<3>::default()).unwrap();
+        std::fs::write(&path, format!("0.5. {state}\n1. {{\n")).unwrap();
+        let error = read_frames(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(io::ErrorKind::InvalidData, error.kind());
+        assert!(error.to_string().starts_with("line 2"));
+    }
 }
@@ -1,7 +1,10 @@
 #![allow(unused, dead_code)]
 
 use crate::{
-    boundaries::BoundaryConditions, potential::PotentialEnergy, prop::Props, state::MolecularState,
+    boundaries::BoundaryConditions,
+    potential::PotentialEnergy,
+    prop::Props,
+    state::{self, MolecularState},
 };
 use d_vector::{DVector, Real};
 use std::{cell::RefMut, ops::AddAssign};
@@ -11,15 +14,32 @@ pub fn single_step<const D: usize>(
     pos: &mut [DVector<D>],
     vel: &mut [DVector<D>],
     acc: &mut [DVector<D>],
+    masses: &[Real],
     boundaries: &dyn BoundaryConditions<D>,
     potential_energy: &dyn PotentialEnergy<D>,
 ) {
     leapfrog_begin(delta_t, pos, vel, acc);
     apply_boundary_conditions(boundaries, pos);
-    potential_energy.compute_forces(pos, acc, boundaries);
+    compute_accelerations(potential_energy, pos, acc, masses, boundaries);
     leapfrog_end(delta_t, vel, acc);
 }
 
+/// Forces from the potential divided by the molecule masses.
+pub fn compute_accelerations<const D: usize>(
+    potential_energy: &dyn PotentialEnergy<D>,
+    pos: &[DVector<D>],
+    acc: &mut [DVector<D>],
+    masses: &[Real],
+    boundaries: &dyn BoundaryConditions<D>,
+) {
+    potential_energy.compute_forces(pos, acc, boundaries);
+    if !masses.is_empty() {
+        for (j, acceleration) in acc.iter_mut().enumerate() {
+            *acceleration = (1. / state::mass(masses, j)) * &*acceleration;
+        }
+    }
+}
+
 pub fn apply_boundary_conditions<const D: usize>(
     boundaries: &dyn BoundaryConditions<D>,
     pos: &mut [DVector<D>],
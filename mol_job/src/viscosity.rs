@@ -0,0 +1,143 @@
+#![allow(unused, dead_code)]
+
+use crate::{
+    barostat,
+    boundaries::BoundaryConditions,
+    correlation::GreenKubo,
+    potential::PotentialEnergy,
+    prop::{PropSummary, PropTable, Props},
+    thermostat,
+};
+use d_vector::{DVector, Real};
+
+/// Off-diagonal elements `P_ab`, `a < b`, of a pressure tensor.
+pub fn off_diagonal<const D: usize>(tensor: &[[Real; D]; D]) -> Vec<Real> {
+    let mut result = Vec::with_capacity(D * (D - 1) / 2);
+    for (a, row) in tensor.iter().enumerate() {
+        result.extend_from_slice(&row[a + 1..]);
+    }
+    result
+}
+
+/// Shear viscosity `η = V / T ∫ <P_ab(0) P_ab(t)> dt`, the stress autocorrelation
+/// being averaged over the off-diagonal elements of the pressure tensor. Needs a
+/// periodic box for the volume.
+#[derive(Debug)]
+pub struct ShearViscosity<const D: usize>(GreenKubo);
+
+impl<const D: usize> ShearViscosity<D> {
+    pub fn new(n_values: usize, n_origins: usize) -> Self {
+        Self(GreenKubo::new(
+            "stress_acf",
+            "viscosity",
+            n_values,
+            n_origins,
+        ))
+    }
+
+    pub fn every(self, steps: usize) -> Self {
+        Self(self.0.every(steps))
+    }
+
+    pub fn step_avg(self, steps: usize) -> Self {
+        Self(self.0.step_avg(steps))
+    }
+
+    pub fn sample(&self, tensor: &[[Real; D]; D], temperature: Real, volume: Real) {
+        self.0.sample(&off_diagonal(tensor), temperature, volume);
+    }
+
+    /// Columns `t`, the normalized stress `acf` and the running `viscosity` integral
+    /// of the current block.
+    pub fn table(&self) -> PropTable {
+        let components = (D * (D - 1) / 2).max(1) as Real;
+        self.0
+            .table(components, |temperature, volume| volume / temperature)
+    }
+
+    /// Mean and standard error of the viscosity over the completed blocks.
+    pub fn viscosity(&self) -> Option<(Real, Real)> {
+        self.0.estimate()
+    }
+}
+
+impl<const D: usize> Props<D> for ShearViscosity<D> {
+    fn reset(&self) {
+        self.0.reset();
+    }
+
+    fn eval_props(
+        &self,
+        u: &dyn PotentialEnergy<D>,
+        pos: &[DVector<D>],
+        vel: &[DVector<D>],
+        masses: &[Real],
+        boundaries: &dyn BoundaryConditions<D>,
+        unwrapped: &[DVector<D>],
+        delta_t: Real,
+        time: Real,
+    ) {
+        if !self.0.step(delta_t) {
+            return;
+        }
+        if let Some(volume) = barostat::volume(boundaries) {
+            let tensor = barostat::pressure_tensor(vel, masses, u, volume);
+            self.sample(&tensor, thermostat::temperature(vel, masses), volume);
+        }
+    }
+
+    fn accum_props(&self) {}
+
+    fn need_avg(&self, step_count: usize) -> bool {
+        self.0.need_avg(step_count)
+    }
+
+    fn avg_props(&self) {
+        self.0.avg_props(self.table());
+    }
+
+    fn summary(&self) -> Option<PropSummary> {
+        self.0.summary()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        initial_state,
+        job::{Job, JobSetup},
+        lennard_jones::LennardJones,
+    };
+
+    #[test]
+    fn off_diagonal_elements() {
+        assert_eq!(
+            vec![2., 3., 6.],
+            off_diagonal(&[[1., 2., 3.], [4., 5., 6.], [7., 8., 9.]])
+        );
+    }
+
+    #[test]
+    fn wca_fluid_viscosity() {
+        let (boundaries, pos) = initial_state::cubic_lattice(216, 0.8);
+        let mut job: Job<3> = JobSetup::build()
+            .boundaries(boundaries)
+            .init_pos(pos)
+            .random_vel(1.)
+            .potential(LennardJones::new((2 as Real).powf(1. / 6.)))
+            .props(ShearViscosity::new(100, 10).step_avg(500))
+            .job();
+        job.run(100);
+        let tensor = job.pressure_tensor().unwrap();
+        let trace = (0..3).map(|a| tensor[a][a]).sum::<Real>() / 3.;
+        assert!((trace - job.pressure().unwrap()).abs() < 1e-3 * trace.abs());
+        assert!((tensor[0][1] - tensor[1][0]).abs() < 1e-4);
+
+        job.run(1900);
+        let summary = job.props().summary().unwrap();
+        let viscosity = summary.get("viscosity").unwrap();
+        assert!(viscosity.mean > 0.5 && viscosity.mean < 5.);
+        assert!(viscosity.std_dev > 0.);
+    }
+}